@@ -0,0 +1,207 @@
+use crate::expr::LiteralValue;
+use std::cell::RefCell;
+
+// Deep enough for recursive scripts, main sizes the native stack the interpreter
+// runs on from the limit
+pub const DEFAULT_MAX_DEPTH: usize = 5000;
+
+// Stack of a thread from std::thread::spawn, engines on threads with a smaller
+// one have to be told its size
+pub const DEFAULT_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+// Native stack left over for what runs before the outermost call and between
+// two nested calls, a call takes about 95KiB at most in debug builds
+const STACK_RESERVE: usize = 512 * 1024;
+
+// Zawa level call stack. It is kept apart from the native stack so that deep
+// recursion ends in a runtime error instead of crashing the process.
+struct CallStack {
+    frames: Vec<Frame>,
+    max_depth: usize,
+    // Set by a `return f(...)` in a function body, run by `invoke` once the body has returned
+    tail_call: Option<(LiteralValue, Vec<LiteralValue>)>,
+    // Frames of the innermost call an error came out of, kept until the error is caught
+    trace: Option<Vec<Frame>>,
+    // Size of the native stack of the thread
+    stack_size: usize,
+    // Native stack address of the outermost call
+    stack_base: usize,
+}
+
+#[derive(Clone)]
+pub struct Frame {
+    pub name: String,
+    // Line of the call site
+    pub line_number: usize,
+}
+
+thread_local! {
+    static CALL_STACK: RefCell<CallStack> = const {
+        RefCell::new(CallStack {
+            frames: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            tail_call: None,
+            trace: None,
+            stack_size: DEFAULT_STACK_SIZE,
+            stack_base: 0,
+        })
+    };
+}
+
+pub fn set_max_depth(max_depth: usize) {
+    CALL_STACK.with(|stack| stack.borrow_mut().max_depth = max_depth);
+}
+
+pub fn max_depth() -> usize {
+    CALL_STACK.with(|stack| stack.borrow().max_depth)
+}
+
+pub fn set_stack_size(stack_size: usize) {
+    CALL_STACK.with(|stack| stack.borrow_mut().stack_size = stack_size);
+}
+
+// Line the innermost call was made on, 0 outside of any call
+pub fn line_number() -> usize {
+    CALL_STACK.with(|stack| stack.borrow().frames.last().map_or(0, |frame| frame.line_number))
+}
+
+// Calls `callee` from a call site on `line_number` with a frame of its own
+pub fn call(callee: &LiteralValue, args: Vec<LiteralValue>, line_number: usize) -> Result<LiteralValue, String> {
+    let name = match callee {
+        LiteralValue::Callable { name, .. } => name.clone(),
+        other => return Err(format!("line: {}, {} is not callable", line_number, other.to_type())),
+    };
+
+    let address = stack_address();
+    CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if stack.frames.is_empty() {
+            stack.stack_base = address;
+        }
+
+        if stack.frames.len() >= stack.max_depth {
+            stack.trace = Some(stack.frames.clone());
+            return Err(format!("line: {}, Stack overflow, more than {} nested calls", line_number, stack.max_depth));
+        }
+
+        let used = stack.stack_base.abs_diff(address);
+        if used > stack.stack_size.saturating_sub(STACK_RESERVE) {
+            stack.trace = Some(stack.frames.clone());
+            return Err(format!("line: {}, Stack overflow, {} nested calls used up the native stack", line_number, stack.frames.len()));
+        }
+
+        stack.frames.push(Frame { name, line_number });
+        Ok(())
+    })?;
+
+    let result = invoke(callee, args);
+    CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if result.is_err() && stack.trace.is_none() {
+            stack.trace = Some(stack.frames.clone());
+        }
+        stack.frames.pop();
+    });
+    result
+}
+
+// Roughly where the native stack of the current thread ends
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+// Runs a callable along with every tail call it ends in, each one replaces the
+// frame of the call before it. Callables must only be called through here.
+pub fn invoke(callee: &LiteralValue, args: Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    let mut callee = callee.clone();
+    let mut args = args;
+
+    loop {
+        let value = match &callee {
+            LiteralValue::Callable { fn_, .. } => fn_(&args)?,
+            other => return Err(format!("{} is not callable", other.to_type())),
+        };
+
+        let tail_call = CALL_STACK.with(|stack| stack.borrow_mut().tail_call.take());
+        match tail_call {
+            Some((next, next_args)) => {
+                if let LiteralValue::Callable { name, .. } = &next {
+                    CALL_STACK.with(|stack| {
+                        if let Some(frame) = stack.borrow_mut().frames.last_mut() {
+                            frame.name = name.clone();
+                        }
+                    });
+                }
+
+                callee = next;
+                args = next_args;
+            },
+            None => return Ok(value),
+        }
+    }
+}
+
+// The body making the tail call has to return right after this
+pub fn tail_call(callee: LiteralValue, args: Vec<LiteralValue>) {
+    CALL_STACK.with(|stack| stack.borrow_mut().tail_call = Some((callee, args)));
+}
+
+// Frames the error being handled was raised in, the current ones when it was
+// raised before leaving any call. Innermost call last.
+pub fn take_trace() -> Vec<Frame> {
+    CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        match stack.trace.take() {
+            Some(trace) => trace,
+            None => stack.frames.clone(),
+        }
+    })
+}
+
+// Innermost call last, runs of the same frame are folded into one line
+pub fn backtrace(frames: &[Frame]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut index = 0;
+
+    while index < frames.len() {
+        let frame = &frames[index];
+        let repeated = frames[index..]
+            .iter()
+            .take_while(|other| other.name == frame.name && other.line_number == frame.line_number)
+            .count();
+
+        let line = format!("  in {}", frame);
+        if repeated > 1 {
+            lines.push(format!("{} ({} times)", line, repeated));
+        } else {
+            lines.push(line);
+        }
+        index += repeated;
+    }
+
+    lines.join("\n")
+}
+
+impl std::fmt::Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} called on line {}", self.name, self.line_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_frames_are_folded() {
+        let frames = vec![
+            Frame { name: String::from("main"), line_number: 9 },
+            Frame { name: String::from("count"), line_number: 3 },
+            Frame { name: String::from("count"), line_number: 3 },
+            Frame { name: String::from("count"), line_number: 3 },
+        ];
+
+        assert_eq!(backtrace(&frames), "  in main called on line 9\n  in count called on line 3 (3 times)");
+    }
+}
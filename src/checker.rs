@@ -0,0 +1,650 @@
+use crate::environment::Environment;
+use crate::expr::{EnumType, Expr, LiteralValue};
+use crate::pattern::Pattern;
+use crate::stmt::{FunctionKind, Stmt};
+use crate::tokenizer::{Token, TokenType};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// Static type of an expression as far as the checker can tell.
+// `Any` is used for everything it can not infer and is compatible with every type
+#[derive(Clone, PartialEq)]
+pub enum Type {
+    Any,
+    Number,
+    String,
+    Boolean,
+    Null,
+    List,
+    Map,
+    Tuple,
+    Callable { params: Vec<(String, Type)>, returns: Box<Type> },
+    Enum { name: String },
+    Variant { enum_name: String },
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::Boolean => write!(f, "Boolean"),
+            Type::Null => write!(f, "Null"),
+            Type::List => write!(f, "List"),
+            Type::Map => write!(f, "Map"),
+            Type::Tuple => write!(f, "Tuple"),
+            Type::Callable { .. } => write!(f, "Callable"),
+            Type::Enum { .. } => write!(f, "Enum"),
+            Type::Variant { enum_name } => write!(f, "{}", enum_name),
+        }
+    }
+}
+
+impl Type {
+    fn from_value(value: &LiteralValue) -> Self {
+        match value {
+            LiteralValue::Number(_) => Type::Number,
+            LiteralValue::StringValue(_) => Type::String,
+            LiteralValue::True | LiteralValue::False => Type::Boolean,
+            LiteralValue::Null => Type::Null,
+            LiteralValue::List(_) => Type::List,
+            LiteralValue::Map(_) => Type::Map,
+            LiteralValue::Tuple(_) => Type::Tuple,
+            LiteralValue::Callable { name, params, .. } => Type::Callable {
+                params: params.iter().map(|param| (param.clone(), Type::Any)).collect(),
+                returns: Box::new(native_return_type(name)),
+            },
+            LiteralValue::Enum(enum_type) => Type::Enum { name: enum_type.name.clone() },
+            LiteralValue::Variant(variant) => Type::Variant { enum_name: variant.enum_name.clone() },
+            LiteralValue::Class(_) | LiteralValue::Trait(_) | LiteralValue::Instance(_) | LiteralValue::Generator(_) | LiteralValue::Task(_)
+            | LiteralValue::Native(_) => Type::Any,
+        }
+    }
+
+    // Callables are compatible regardless of their signature
+    fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Callable { .. }, Type::Callable { .. }) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+fn native_return_type(name: &str) -> Type {
+    match name {
+        "time" | "number" | "len" => Type::Number,
+        "string" | "type" | "read" | "readln" | "read_file" | "tag" => Type::String,
+        "boolean" | "implements" => Type::Boolean,
+        "print" | "println" | "push" | "exit" | "write_file" => Type::Null,
+        "payload" => Type::Tuple,
+        "args" => Type::List,
+        _ => Type::Any,
+    }
+}
+
+#[derive(Clone)]
+struct Variable {
+    type_: Type,
+    annotated: bool,
+    // Unannotated let binding, its type is the one of its initializer
+    inferred: bool,
+}
+
+// Type inference pass used by `zawa check`, it never runs the program.
+// Annotated names keep their type. The checker does not follow the order the
+// program runs in, so an unannotated binding is Any when it is assigned to
+// anywhere or read inside a function, which can run at any point.
+pub struct Checker {
+    scopes: Vec<HashMap<String, Variable>>,
+    enums: HashMap<String, Rc<EnumType>>,
+    // Declared return type of every function being checked, innermost last
+    return_types: Vec<(String, Type)>,
+    // Number of scopes outside of every function being checked, innermost last
+    functions: Vec<usize>,
+    // Names assigned to anywhere in the program, found by a first pass
+    assigned: HashSet<String>,
+    errors: Vec<String>,
+}
+
+impl Checker {
+    pub fn new(globals: &Environment) -> Self {
+        let global_scope = globals
+            .constant_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = globals.get(&name)?;
+                Some((name, Variable { type_: Type::from_value(&value), annotated: true, inferred: false }))
+            })
+            .collect();
+
+        Self {
+            scopes: vec![global_scope],
+            enums: HashMap::new(),
+            return_types: Vec::new(),
+            functions: Vec::new(),
+            assigned: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        // The first pass only collects the assigned names
+        let globals = self.scopes.clone();
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+        self.scopes = globals;
+        self.enums.clear();
+        self.errors.clear();
+
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors.join("\n"))
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expression } | Stmt::Echo { expression } => {
+                self.check_expr(expression);
+            },
+            Stmt::Let { keyword, pattern, type_annotation, initializer } => {
+                let value_type = self.check_expr(initializer);
+                let annotated = type_annotation.as_ref().map(|annotation| self.resolve_type(annotation));
+
+                if let Some(annotated) = &annotated
+                    && !annotated.accepts(&value_type) {
+                    self.errors.push(format!(
+                        "line: {}, {} is declared as {} but initialised with {}",
+                        keyword.line_number, pattern, annotated, value_type
+                    ));
+                }
+
+                match (pattern, annotated) {
+                    (Pattern::Binding { name }, Some(annotated)) => self.declare(name, annotated, true),
+                    (Pattern::Binding { name }, None) if self.assigned.contains(&name.lexeme) => self.declare(name, Type::Any, false),
+                    (Pattern::Binding { name }, None) => self.declare_inferred(name, value_type),
+                    (pattern, _) => self.declare_pattern(pattern),
+                }
+            },
+            Stmt::Const { name, initializer } => {
+                let value_type = self.check_expr(initializer);
+                self.declare(name, value_type, true);
+            },
+            Stmt::Block { statements } => {
+                self.scopes.push(HashMap::new());
+                for statement in statements {
+                    self.check_stmt(statement);
+                }
+                self.scopes.pop();
+            },
+            Stmt::If { predicate, then, otherwise } => {
+                self.check_expr(predicate);
+                self.check_stmt(then);
+                if let Some(otherwise) = otherwise {
+                    self.check_stmt(otherwise);
+                }
+            },
+            Stmt::While { condition, body } => {
+                self.check_expr(condition);
+                self.check_stmt(body);
+            },
+            Stmt::Break => (),
+            Stmt::Function { name, params, param_types, return_type, body, kind } => {
+                let param_types: Vec<Type> = param_types
+                    .iter()
+                    .map(|annotation| match annotation {
+                        Some(annotation) => self.resolve_type(annotation),
+                        None => Type::Any,
+                    })
+                    .collect();
+                // Calling a generator never runs its body, so its returns are not checked
+                let returns = match return_type {
+                    Some(annotation) if *kind != FunctionKind::Generator => self.resolve_type(annotation),
+                    _ => Type::Any,
+                };
+
+                // An async function returns a task, the annotation is the type it finishes with
+                let signature = Type::Callable {
+                    params: params.iter().map(|p| p.param_name()).zip(param_types.iter().cloned()).collect(),
+                    returns: Box::new(if *kind == FunctionKind::Normal { returns.clone() } else { Type::Any }),
+                };
+                self.declare(name, signature, false);
+
+                self.functions.push(self.scopes.len());
+                self.scopes.push(HashMap::new());
+                for (param, param_type) in params.iter().zip(param_types) {
+                    match param {
+                        Pattern::Binding { name } => self.declare(name, param_type, true),
+                        pattern => self.declare_pattern(pattern),
+                    }
+                }
+                self.return_types.push((name.lexeme.clone(), returns));
+                for stmt in body {
+                    self.check_stmt(stmt);
+                }
+                self.return_types.pop();
+                self.scopes.pop();
+                self.functions.pop();
+            },
+            Stmt::Return { keyword, value } => {
+                let value_type = match value {
+                    Some(value) => self.check_expr(value),
+                    None => Type::Null,
+                };
+
+                if let Some((name, expected)) = self.return_types.last()
+                    && !expected.accepts(&value_type) {
+                    self.errors.push(format!(
+                        "line: {}, Function {} should return {} but returns {}",
+                        keyword.line_number, name, expected, value_type
+                    ));
+                }
+            },
+            Stmt::ForIn { keyword: _, pattern, iterable, body } => {
+                self.check_expr(iterable);
+                self.scopes.push(HashMap::new());
+                self.declare_pattern(pattern);
+                self.check_stmt(body);
+                self.scopes.pop();
+            },
+            Stmt::Yield { keyword: _, value } => {
+                if let Some(value) = value {
+                    self.check_expr(value);
+                }
+            },
+            Stmt::Try { body, name, handler } => {
+                self.check_stmt(body);
+                self.scopes.push(HashMap::new());
+                self.declare(name, Type::Any, false);
+                self.check_stmt(handler);
+                self.scopes.pop();
+            },
+            Stmt::Enum { name, variants } => {
+                self.enums.insert(name.lexeme.clone(), Rc::new(EnumType::from_declaration(name, variants)));
+                self.declare(name, Type::Enum { name: name.lexeme.clone() }, true);
+            },
+            Stmt::Class { name, traits: _, methods } => {
+                self.declare(name, Type::Any, false);
+                self.check_methods(methods);
+            },
+            Stmt::Trait { name, signatures: _, defaults } => {
+                self.declare(name, Type::Any, false);
+                self.check_methods(defaults);
+            },
+            Stmt::Assert { keyword: _, condition, message } => {
+                self.check_expr(condition);
+                if let Some(message) = message {
+                    self.check_expr(message);
+                }
+            },
+            Stmt::Test { keyword: _, name: _, body } => self.check_stmt(body),
+        }
+    }
+
+    // Methods are checked like functions, but their names are not variables
+    fn check_methods(&mut self, methods: &[Stmt]) {
+        self.scopes.push(HashMap::new());
+        for method in methods {
+            self.check_stmt(method);
+        }
+        self.scopes.pop();
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::AnonFunction { paren: _, arguments, body } => {
+                self.functions.push(self.scopes.len());
+                self.scopes.push(HashMap::new());
+                for argument in arguments {
+                    self.declare_pattern(argument);
+                }
+                self.return_types.push((String::from("anonymous function"), Type::Any));
+                for stmt in body {
+                    self.check_stmt(stmt);
+                }
+                self.return_types.pop();
+                self.scopes.pop();
+                self.functions.pop();
+
+                Type::Callable {
+                    params: arguments.iter().map(|p| (p.param_name(), Type::Any)).collect(),
+                    returns: Box::new(Type::Any),
+                }
+            },
+            Expr::Binary { left, operator, right } => {
+                let left = self.check_expr(left);
+                let right = self.check_expr(right);
+                self.binary_type(&left, operator, &right)
+            },
+            Expr::Grouping { expression } => self.check_expr(expression),
+            Expr::Call { callee, paren, arguments, keyword_arguments } => {
+                let callee_type = self.check_expr(callee);
+                let argument_types: Vec<Type> = arguments.iter().map(|argument| self.check_expr(argument)).collect();
+                let keyword_types: Vec<(&Token, Type)> = keyword_arguments
+                    .iter()
+                    .map(|(name, argument)| (name, self.check_expr(argument)))
+                    .collect();
+
+                match callee_type {
+                    Type::Callable { params, returns } => {
+                        self.check_arguments(callee, paren, &params, &argument_types, &keyword_types);
+                        *returns
+                    },
+                    Type::Any => Type::Any,
+                    other => {
+                        self.errors.push(format!("line: {}, {} is not callable", paren.line_number, other));
+                        Type::Any
+                    },
+                }
+            },
+            Expr::Literal { value } => Type::from_value(value),
+            Expr::Logical { left, operator: _, right } => {
+                let left = self.check_expr(left);
+                let right = self.check_expr(right);
+                if left == right { left } else { Type::Any }
+            },
+            Expr::Unary { operator, right } => {
+                let right = self.check_expr(right);
+                match operator.token_type {
+                    TokenType::Minus => {
+                        if !Type::Number.accepts(&right) {
+                            self.errors.push(format!("line: {}, Minus not implemented for {}", operator.line_number, right));
+                        }
+                        Type::Number
+                    },
+                    _ => Type::Boolean,
+                }
+            },
+            Expr::Variable { name } => self.variable_type(&name.lexeme),
+            Expr::Assign { name, value } => {
+                let value_type = self.check_expr(value);
+                self.assign(name, &value_type);
+                value_type
+            },
+            Expr::List { elements } => {
+                for element in elements {
+                    self.check_expr(element);
+                }
+                Type::List
+            },
+            Expr::Tuple { elements } => {
+                for element in elements {
+                    self.check_expr(element);
+                }
+                Type::Tuple
+            },
+            Expr::Get { object, name } => match self.check_expr(object) {
+                Type::Enum { name: enum_name } => self.variant_type(&enum_name, name),
+                _ => Type::Any,
+            },
+            Expr::Set { object, name: _, value } => {
+                self.check_expr(object);
+                self.check_expr(value)
+            },
+            Expr::Map { brace: _, entries } => {
+                for (key, value) in entries {
+                    self.check_expr(key);
+                    self.check_expr(value);
+                }
+                Type::Map
+            },
+            Expr::Index { object, bracket: _, index } => {
+                let object = self.check_expr(object);
+                self.check_expr(index);
+                if object == Type::String { Type::String } else { Type::Any }
+            },
+            Expr::SetIndex { object, bracket: _, index, value } => {
+                self.check_expr(object);
+                self.check_expr(index);
+                self.check_expr(value)
+            },
+            Expr::Match { keyword: _, subject, arms } => {
+                self.check_expr(subject);
+
+                let mut arm_types = Vec::new();
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.declare_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.check_expr(guard);
+                    }
+                    arm_types.push(self.check_expr(&arm.body));
+                    self.scopes.pop();
+                }
+
+                match arm_types.first() {
+                    Some(first) if arm_types.iter().all(|t| t == first) => first.clone(),
+                    _ => Type::Any,
+                }
+            },
+            Expr::Await { keyword: _, value } => {
+                self.check_expr(value);
+                Type::Any
+            },
+        }
+    }
+
+    // Mirrors the operand rules of `Expr::Binary` evaluation
+    fn binary_type(&mut self, left: &Type, operator: &Token, right: &Type) -> Type {
+        match operator.token_type {
+            TokenType::EqualEqual | TokenType::BangEqual => return Type::Boolean,
+            TokenType::Plus if *left == Type::Any || *right == Type::Any => {
+                return match (left, right) {
+                    (Type::Number, _) | (_, Type::Number) => Type::Number,
+                    (Type::String, _) | (_, Type::String) => Type::String,
+                    _ => Type::Any,
+                };
+            },
+            _ if *left == Type::Any || *right == Type::Any => return match operator.token_type {
+                TokenType::Minus | TokenType::Star | TokenType::Slash => Type::Number,
+                _ => Type::Boolean,
+            },
+            _ => (),
+        }
+
+        match (left, &operator.token_type, right) {
+            (Type::Number, TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash, Type::Number) => Type::Number,
+            (Type::String, TokenType::Plus, Type::String) => Type::String,
+            (
+                Type::Number, TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual, Type::Number
+            ) | (
+                Type::String, TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual, Type::String
+            ) => Type::Boolean,
+            _ => {
+                self.errors.push(format!(
+                    "line: {}, Binary operator {} cannot be applied for operands of type {} and {}",
+                    operator.line_number, operator.token_type, left, right
+                ));
+                Type::Any
+            },
+        }
+    }
+
+    fn check_arguments(
+        &mut self,
+        callee: &Expr,
+        paren: &Token,
+        params: &[(String, Type)],
+        arguments: &[Type],
+        keyword_arguments: &[(&Token, Type)]
+    ) {
+        let callee_name = match callee {
+            Expr::Variable { name } => name.lexeme.clone(),
+            _ => String::from("callable"),
+        };
+
+        if arguments.len() + keyword_arguments.len() != params.len() {
+            self.errors.push(format!(
+                "line: {}, Callable {} expected {} arguments but {} were given",
+                paren.line_number, callee_name, params.len(), arguments.len() + keyword_arguments.len()
+            ));
+            return;
+        }
+
+        let given = arguments
+            .iter()
+            .zip(params.iter())
+            .map(|(argument, (param, expected))| (param.as_str(), expected, argument))
+            .chain(keyword_arguments.iter().filter_map(|(name, argument)| {
+                let (param, expected) = params.iter().find(|(param, _)| *param == name.lexeme)?;
+                Some((param.as_str(), expected, argument))
+            }));
+
+        for (param, expected, argument) in given {
+            if !expected.accepts(argument) {
+                self.errors.push(format!(
+                    "line: {}, Argument {} of {} should be {} but is {}",
+                    paren.line_number, param, callee_name, expected, argument
+                ));
+            }
+        }
+    }
+
+    fn variant_type(&mut self, enum_name: &str, tag: &Token) -> Type {
+        let variant = self.enums
+            .get(enum_name)
+            .and_then(|enum_type| enum_type.variants.iter().find(|(name, _)| *name == tag.lexeme))
+            .cloned();
+
+        match variant {
+            Some((_, Some(fields))) => Type::Callable {
+                params: fields.into_iter().map(|field| (field, Type::Any)).collect(),
+                returns: Box::new(Type::Variant { enum_name: enum_name.to_string() }),
+            },
+            Some((_, None)) => Type::Variant { enum_name: enum_name.to_string() },
+            // Enums passed around as values are not tracked
+            None if !self.enums.contains_key(enum_name) => Type::Any,
+            None => {
+                self.errors.push(format!("line: {}, Enum {} has no variant {}", tag.line_number, enum_name, tag.lexeme));
+                Type::Any
+            },
+        }
+    }
+
+    fn resolve_type(&mut self, annotation: &Token) -> Type {
+        match annotation.lexeme.as_str() {
+            "any" => Type::Any,
+            "number" => Type::Number,
+            "string" => Type::String,
+            "bool" | "boolean" => Type::Boolean,
+            "null" => Type::Null,
+            "list" => Type::List,
+            "map" => Type::Map,
+            "tuple" => Type::Tuple,
+            "fn" => Type::Callable { params: Vec::new(), returns: Box::new(Type::Any) },
+            name if self.enums.contains_key(name) => Type::Variant { enum_name: name.to_string() },
+            name => {
+                self.errors.push(format!("line: {}, Unknown type {}", annotation.line_number, name));
+                Type::Any
+            },
+        }
+    }
+
+    fn assign(&mut self, name: &Token, value_type: &Type) {
+        self.assigned.insert(name.lexeme.clone());
+        let Some(variable) = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(&name.lexeme)) else {
+            return;
+        };
+
+        if variable.annotated {
+            if !variable.type_.accepts(value_type) {
+                self.errors.push(format!(
+                    "line: {}, Can not assign {} to {} of type {}",
+                    name.line_number, value_type, name.lexeme, variable.type_
+                ));
+            }
+        } else if variable.type_ != *value_type {
+            variable.type_ = Type::Any;
+        }
+    }
+
+    fn declare(&mut self, name: &Token, type_: Type, annotated: bool) {
+        let scope = self.scopes.last_mut().expect("Checker has no scope");
+        scope.insert(name.lexeme.clone(), Variable { type_, annotated, inferred: false });
+    }
+
+    fn declare_inferred(&mut self, name: &Token, type_: Type) {
+        let scope = self.scopes.last_mut().expect("Checker has no scope");
+        scope.insert(name.lexeme.clone(), Variable { type_, annotated: false, inferred: true });
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        for default in pattern.defaults() {
+            self.check_expr(default);
+        }
+        for name in pattern.bindings() {
+            self.declare(name, Type::Any, false);
+        }
+    }
+
+    // Inferred bindings declared outside of the function being checked are Any in it
+    fn variable_type(&self, name: &str) -> Type {
+        let outside = self.functions.last().copied().unwrap_or(0);
+        let found = self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| scope.get(name).map(|variable| (index, variable)));
+
+        match found {
+            Some((index, variable)) if variable.inferred && index < outside => Type::Any,
+            Some((_, variable)) => variable.type_.clone(),
+            None => Type::Any,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::tokenizer::Tokenizer;
+
+    fn check(src: &str) -> Result<(), String> {
+        let tokens = Tokenizer::new(src).tokenize().unwrap();
+        let stmts = Parser::new(tokens).parse().unwrap();
+
+        let mut globals = Environment::new();
+        globals.define_const(String::from("len"), LiteralValue::Callable {
+            name: String::from("len"),
+            arity: 1,
+            params: vec![String::from("arg")],
+            fn_: Rc::new(|_| Ok(LiteralValue::Null))
+        });
+
+        Checker::new(&globals).check(&stmts)
+    }
+
+    #[test]
+    fn unannotated_code_is_accepted() {
+        assert!(check("let a = 1; a = \"text\"; println(a + \"!\");").is_ok());
+        assert!(check("fn f(x) { return x + 1; } f(\"a\");").is_ok());
+        assert!(check("let x = 1; fn g() { return x + \"a\"; } x = \"s\"; println(g());").is_ok());
+        assert!(check("let y = 1; fn h() { return y + \"a\"; }").is_ok());
+    }
+
+    #[test]
+    fn binary_operands_are_checked() {
+        let err = check("let a = 1 + \"a\";").unwrap_err();
+        assert_eq!(err, "line: 1, Binary operator Plus cannot be applied for operands of type Number and String");
+        assert!(check("let a = len(\"a\") - true;").is_err());
+    }
+
+    #[test]
+    fn annotations_are_checked() {
+        assert!(check("let a: number = \"a\";").is_err());
+        assert!(check("let a: number = 1; a = \"a\";").is_err());
+        assert!(check("fn add(a: number, b: number) -> number { return a + b; } add(1, \"2\");").is_err());
+        assert!(check("fn name() -> string { return 1; }").is_err());
+        assert!(check("let a: wat = 1;").is_err());
+        assert!(check("fn add(a: number, b: number) -> number { return a + b; } let c: number = add(1, b: 2);").is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::call_stack;
+use crate::expr::LiteralValue;
+use crate::ordered_map::OrderedMap;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+pub struct ClassValue {
+    pub name: String,
+    // Methods are plain callables, the ones whose first parameter is `self`
+    // get the instance bound when they are looked up on it
+    pub methods: HashMap<String, LiteralValue>,
+    pub traits: Vec<Rc<TraitValue>>,
+}
+
+pub struct TraitValue {
+    pub name: String,
+    // Every method of the trait with its arity, including `self`
+    pub methods: Vec<(String, usize)>,
+    pub defaults: HashMap<String, LiteralValue>,
+}
+
+pub struct InstanceValue {
+    pub class: Rc<ClassValue>,
+    pub fields: RefCell<OrderedMap>,
+}
+
+impl ClassValue {
+    // Callable that creates an instance and runs `init` on it when the class has one
+    pub fn constructor(class: &Rc<ClassValue>) -> LiteralValue {
+        let (arity, params) = match class.methods.get("init") {
+            Some(LiteralValue::Callable { arity, params, .. }) if takes_self(params) => (arity - 1, params[1..].to_vec()),
+            Some(LiteralValue::Callable { arity, params, .. }) => (*arity, params.clone()),
+            _ => (0, Vec::new()),
+        };
+
+        let class_clone = class.clone();
+        let constructor = move |args: &Vec<LiteralValue>| {
+            let instance = LiteralValue::Instance(Rc::new(InstanceValue {
+                class: class_clone.clone(),
+                fields: RefCell::new(OrderedMap::new()),
+            }));
+
+            if let Some(init @ LiteralValue::Callable { params, .. }) = class_clone.methods.get("init") {
+                if takes_self(params) {
+                    let mut all_args = vec![instance.clone()];
+                    all_args.extend(args.iter().cloned());
+                    call_stack::invoke(init, all_args)?;
+                } else {
+                    call_stack::invoke(init, args.clone())?;
+                }
+            }
+
+            Ok(instance)
+        };
+
+        LiteralValue::Callable {
+            name: class.name.clone(),
+            arity,
+            params,
+            fn_: Rc::new(constructor),
+        }
+    }
+
+    pub fn implements(&self, trait_: &Rc<TraitValue>) -> bool {
+        self.traits.iter().any(|t| Rc::ptr_eq(t, trait_))
+    }
+}
+
+impl InstanceValue {
+    // Fields shadow methods of the same name
+    pub fn get(instance: &Rc<InstanceValue>, name: &str) -> Option<LiteralValue> {
+        if let Some(value) = instance.fields.borrow().get(&LiteralValue::StringValue(name.to_string())) {
+            return Some(value.clone());
+        }
+
+        let method = instance.class.methods.get(name)?;
+        Some(bind(method, LiteralValue::Instance(instance.clone())))
+    }
+
+    // Calls a special method such as `__add__`, None if the class does not define it
+    pub fn call_method(
+        instance: &Rc<InstanceValue>,
+        name: &str,
+        args: Vec<LiteralValue>,
+        line_number: usize
+    ) -> Option<Result<LiteralValue, String>> {
+        let method = instance.class.methods.get(name)?;
+
+        match bind(method, LiteralValue::Instance(instance.clone())) {
+            method @ LiteralValue::Callable { arity, .. } if arity == args.len() => Some(call_stack::call(&method, args, line_number)),
+            LiteralValue::Callable { arity, .. } => Some(Err(format!(
+                "line: {}, Method {}.{} should take {} parameters besides self but takes {}",
+                line_number, instance.class.name, name, args.len(), arity
+            ))),
+            _ => None,
+        }
+    }
+
+    pub fn set(&self, name: &str, value: LiteralValue) {
+        self.fields.borrow_mut().insert(LiteralValue::StringValue(name.to_string()), value);
+    }
+}
+
+fn takes_self(params: &[String]) -> bool {
+    params.first().is_some_and(|param| param == "self")
+}
+
+// Passes `instance` as the `self` argument of a method, the bound callable only
+// forwards to the method so tail calls made by it are still run by the caller
+fn bind(method: &LiteralValue, instance: LiteralValue) -> LiteralValue {
+    match method {
+        LiteralValue::Callable { name, arity, params, fn_ } if takes_self(params) => {
+            let fn_ = fn_.clone();
+            let bound = move |args: &Vec<LiteralValue>| {
+                let mut all_args = vec![instance.clone()];
+                all_args.extend(args.iter().cloned());
+                fn_(&all_args)
+            };
+
+            LiteralValue::Callable {
+                name: name.clone(),
+                arity: arity - 1,
+                params: params[1..].to_vec(),
+                fn_: Rc::new(bound),
+            }
+        },
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,175 @@
+// Command line of the zawa binary
+
+pub const USAGE: &str = "\
+Usage: zawa [options] [file | -e code | -] [args...]
+       zawa run <file | -e code | -> [-- args...]
+       zawa check <file | -e code | ->
+       zawa test [dir]
+       zawa tokens <file | -e code | ->
+       zawa ast <file | -e code | ->
+       zawa fmt [--check] [file | dir | -e code | -]
+
+Without a script zawa opens the shell.
+
+Options:
+  -e <code>            Run code given on the command line
+  -                    Read the script from stdin
+  --max-depth <depth>  Limit on nested calls, 5000 by default
+  --check              With fmt, list the files it would change instead of writing them
+  -h, --help           Print this help
+  -V, --version        Print the version";
+
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    File { path: String },
+    Code { code: String },
+    Stdin,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Repl,
+    // `args` are given to the script through the args() native
+    Run { source: Source, args: Vec<String> },
+    Check { source: Source },
+    Test { dir: String },
+    Tokens { source: Source },
+    Ast { source: Source },
+    // A directory formats every zawa file in it, code and stdin go to stdout
+    Fmt { source: Source, check: bool },
+    Help,
+    Version,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub max_depth: Option<usize>,
+}
+
+// Parses the arguments after the program name. Options can come before and after
+// the subcommand, everything after the script goes to the script.
+pub fn parse(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let mut max_depth = None;
+    let mut subcommand = None;
+    let mut check = false;
+
+    let command = loop {
+        let Some(arg) = args.next() else {
+            break match subcommand {
+                None => Command::Repl,
+                Some("test") => Command::Test { dir: String::from(".") },
+                Some("fmt") => Command::Fmt { source: Source::File { path: String::from(".") }, check },
+                Some(name) => return Err(format!("{} expects a script", name)),
+            };
+        };
+
+        let source = match arg.as_str() {
+            "--max-depth" => {
+                let depth = args.next().ok_or("--max-depth expects a number of nested calls")?;
+                max_depth = Some(depth.parse().map_err(|_| format!("{} is not a number of nested calls", depth))?);
+                continue;
+            },
+            "--check" => {
+                check = true;
+                continue;
+            },
+            "-h" | "--help" => break Command::Help,
+            "-V" | "--version" => break Command::Version,
+            "-e" => Source::Code { code: args.next().ok_or("-e expects code to run")?.clone() },
+            "-" => Source::Stdin,
+            "run" | "check" | "test" | "tokens" | "ast" | "fmt" if subcommand.is_none() => {
+                subcommand = Some(arg.as_str());
+                continue;
+            },
+            option if option.starts_with('-') => return Err(format!("Unknown option {}", option)),
+            path => Source::File { path: path.to_string() },
+        };
+
+        let rest: Vec<String> = args.by_ref().cloned().collect();
+        let script_args = match rest.first().map(|arg| arg.as_str()) {
+            Some("--") => rest[1..].to_vec(),
+            _ => rest,
+        };
+
+        break match subcommand {
+            None | Some("run") => Command::Run { source, args: script_args },
+            Some("test") => match source {
+                Source::File { path } if script_args.is_empty() => Command::Test { dir: path },
+                _ => return Err(String::from("test expects a directory")),
+            },
+            Some(name) if !script_args.is_empty() => return Err(format!("{} expects one script", name)),
+            Some("check") => Command::Check { source },
+            Some("tokens") => Command::Tokens { source },
+            Some("fmt") => Command::Fmt { source, check },
+            _ => Command::Ast { source },
+        };
+    };
+
+    if check && !matches!(command, Command::Fmt { .. } | Command::Help | Command::Version) {
+        return Err(String::from("--check only goes with fmt"));
+    }
+
+    Ok(Options { command, max_depth })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Options, String> {
+        parse(&args.split_whitespace().map(String::from).collect::<Vec<String>>())
+    }
+
+    fn file(path: &str) -> Source {
+        Source::File { path: path.to_string() }
+    }
+
+    #[test]
+    fn commands_are_parsed() {
+        assert_eq!(parse_str("").unwrap().command, Command::Repl);
+        assert_eq!(parse_str("a.zw").unwrap().command, Command::Run { source: file("a.zw"), args: Vec::new() });
+        assert_eq!(
+            parse_str("run a.zw -- x --max-depth").unwrap().command,
+            Command::Run { source: file("a.zw"), args: vec![String::from("x"), String::from("--max-depth")] }
+        );
+        assert_eq!(
+            parse(&[String::from("-e"), String::from("println(1);"), String::from("x")]).unwrap().command,
+            Command::Run { source: Source::Code { code: String::from("println(1);") }, args: vec![String::from("x")] }
+        );
+        assert_eq!(parse_str("- x").unwrap().command, Command::Run { source: Source::Stdin, args: vec![String::from("x")] });
+        assert_eq!(parse_str("check a.zw").unwrap().command, Command::Check { source: file("a.zw") });
+        assert_eq!(parse_str("test").unwrap().command, Command::Test { dir: String::from(".") });
+        assert_eq!(parse_str("test lib").unwrap().command, Command::Test { dir: String::from("lib") });
+        assert_eq!(parse_str("tokens -").unwrap().command, Command::Tokens { source: Source::Stdin });
+        assert_eq!(parse_str("ast a.zw").unwrap().command, Command::Ast { source: file("a.zw") });
+        assert_eq!(parse_str("fmt").unwrap().command, Command::Fmt { source: file("."), check: false });
+        assert_eq!(parse_str("fmt --check src").unwrap().command, Command::Fmt { source: file("src"), check: true });
+        assert_eq!(parse_str("--version").unwrap().command, Command::Version);
+        assert_eq!(parse_str("run -h").unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn max_depth_goes_anywhere_before_the_script() {
+        let options = parse_str("--max-depth 100 a.zw").unwrap();
+        assert_eq!(options.max_depth, Some(100));
+
+        let options = parse_str("run --max-depth 10 a.zw").unwrap();
+        assert_eq!(options.max_depth, Some(10));
+        assert_eq!(options.command, Command::Run { source: file("a.zw"), args: Vec::new() });
+    }
+
+    #[test]
+    fn wrong_arguments_are_errors() {
+        assert!(parse_str("--max-depth").is_err());
+        assert!(parse_str("--max-depth many a.zw").is_err());
+        assert!(parse_str("-e").is_err());
+        assert!(parse_str("--wat").is_err());
+        assert!(parse_str("check").is_err());
+        assert!(parse_str("check a.zw b.zw").is_err());
+        assert!(parse_str("test -e 1").is_err());
+        assert!(parse_str("--check a.zw").is_err());
+        assert!(parse_str("fmt a.zw b.zw").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+// Tab completion of the shell: keywords, globals, meta-commands and paths after :load
+
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use zawa::LiteralValue;
+
+pub struct ShellHelper {
+    // Globals of the session and the arity of the ones that are callables
+    names: Vec<(String, Option<usize>)>,
+    files: FilenameCompleter,
+}
+
+impl ShellHelper {
+    pub fn new() -> Self {
+        Self { names: Vec::new(), files: FilenameCompleter::new() }
+    }
+
+    // Called before every prompt, the session defines new names as it goes
+    pub fn set_globals(&mut self, globals: Vec<(String, LiteralValue)>) {
+        self.names = globals
+            .into_iter()
+            .map(|(name, value)| match value {
+                LiteralValue::Callable { arity, .. } => (name, Some(arity)),
+                _ => (name, None),
+            })
+            .collect();
+    }
+
+    // Keywords and globals starting with `prefix`, callables are listed with their arity
+    fn candidates(&self, prefix: &str) -> Vec<Pair> {
+        let keywords = zawa::keywords().into_iter().map(|keyword| (keyword.to_string(), None));
+        let mut candidates: Vec<Pair> = keywords
+            .chain(self.names.iter().cloned())
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, arity)| Pair {
+                display: match arity {
+                    Some(arity) => format!("{}/{}", name, arity),
+                    None => name.clone(),
+                },
+                replacement: name,
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.replacement.cmp(&b.replacement));
+        candidates
+    }
+
+    // Start of what is being completed and the candidates for it
+    fn complete_line(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let before = &line[..pos];
+
+        if before.starts_with(":load ") {
+            return self.files.complete_path(line, pos).unwrap_or((pos, Vec::new()));
+        }
+        if before.starts_with(':') && !before.contains(char::is_whitespace) {
+            let commands = crate::repl::COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(before))
+                .map(|command| Pair { display: command.to_string(), replacement: command.to_string() })
+                .collect();
+            return (0, commands);
+        }
+
+        let start = before.trim_end_matches(|c: char| c.is_ascii_alphanumeric() || c == '_').len();
+        let word = &before[start..];
+        // Fields after a '.' are not known before the code runs
+        if word.is_empty() || before[..start].ends_with('.') {
+            return (pos, Vec::new());
+        }
+
+        (start, self.candidates(word))
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.complete_line(line, pos))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zawa::Engine;
+
+    fn displays(helper: &ShellHelper, line: &str) -> (usize, Vec<String>) {
+        let (start, pairs) = helper.complete_line(line, line.len());
+        (start, pairs.into_iter().map(|pair| pair.display).collect())
+    }
+
+    #[test]
+    fn keywords_globals_and_commands_are_completed() {
+        let mut engine = Engine::new();
+        engine.eval("fn print_all(a, b) {} let printed = 1;").unwrap();
+
+        let mut helper = ShellHelper::new();
+        helper.set_globals(engine.globals());
+
+        assert_eq!(displays(&helper, "let x = pri"), (8, vec![
+            String::from("print/1"),
+            String::from("print_all/2"),
+            String::from("printed"),
+            String::from("println/1"),
+        ]));
+        assert_eq!(displays(&helper, "  whi"), (2, vec![String::from("while")]));
+        assert_eq!(displays(&helper, "readl"), (0, vec![String::from("readln/0")]));
+        assert_eq!(displays(&helper, ":t"), (0, vec![String::from(":type"), String::from(":time")]));
+        assert_eq!(displays(&helper, "point.pri"), (9, Vec::new()));
+        assert_eq!(displays(&helper, "x + "), (4, Vec::new()));
+    }
+
+    #[test]
+    fn paths_are_completed_after_load() {
+        let directory = std::env::temp_dir().join("zawa_completion");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("script.zw"), "").unwrap();
+
+        let helper = ShellHelper::new();
+        let line = format!(":load {}/scr", directory.display());
+        let (start, pairs) = helper.complete_line(&line, line.len());
+
+        assert_eq!(start, 6);
+        assert_eq!(pairs.len(), 1);
+        assert!(pairs[0].replacement.ends_with("script.zw"));
+    }
+}
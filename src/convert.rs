@@ -0,0 +1,274 @@
+use crate::call_stack;
+use crate::expr::LiteralValue;
+use crate::native_object::NativeObject;
+use crate::sandbox;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Rust values a Zawa value can be taken as, used for the arguments of registered
+// functions and for values read out of an engine
+pub trait FromValue: Sized {
+    fn from_value(value: &LiteralValue) -> Result<Self, String>;
+}
+
+// Rust values that can be handed to Zawa
+pub trait IntoValue {
+    fn into_value(self) -> LiteralValue;
+}
+
+// What a registered function can return, a value or a runtime error
+pub trait IntoResult {
+    fn into_result(self) -> Result<LiteralValue, String>;
+}
+
+// Argument lists for calling Zawa functions from Rust
+pub trait IntoArgs {
+    fn into_args(self) -> Vec<LiteralValue>;
+}
+
+// Rust closures that can be registered as natives, `Args` is the tuple of their argument types
+pub trait RegisterFn<Args> {
+    fn into_callable(self, name: &str) -> LiteralValue;
+}
+
+// Registered functions are held to the size limit like the built in ones
+fn check_size(value: &LiteralValue) -> Result<(), String> {
+    match value {
+        LiteralValue::StringValue(s) => sandbox::check_size("String", s.len()),
+        LiteralValue::List(items) => sandbox::check_size("List", items.borrow().len()),
+        LiteralValue::Map(map) => sandbox::check_size("Map", map.borrow().len()),
+        _ => Ok(()),
+    }
+}
+
+fn expected(type_: &str, value: &LiteralValue) -> String {
+    format!("Expected a {} but got {}", type_, value.to_type())
+}
+
+impl FromValue for LiteralValue {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Number(x) => Ok(*x),
+            other => Err(expected("Number", other)),
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Number(x) if x.fract() == 0.0 && x.abs() <= i64::MAX as f64 => Ok(*x as i64),
+            LiteralValue::Number(x) => Err(format!("{} is not a whole number", x)),
+            other => Err(expected("Number", other)),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::True => Ok(true),
+            LiteralValue::False => Ok(false),
+            other => Err(expected("Boolean", other)),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::StringValue(s) => Ok(s.clone()),
+            other => Err(expected("String", other)),
+        }
+    }
+}
+
+// Null becomes None
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+// Lists and tuples
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::List(items) => items.borrow().iter().map(T::from_value).collect(),
+            LiteralValue::Tuple(items) => items.iter().map(T::from_value).collect(),
+            other => Err(expected("List", other)),
+        }
+    }
+}
+
+// Native objects are taken back as the host type they were created with
+impl<T: NativeObject> FromValue for Rc<T> {
+    fn from_value(value: &LiteralValue) -> Result<Self, String> {
+        match value {
+            LiteralValue::Native(object) => {
+                let type_name = object.type_name().to_string();
+                let object: Rc<dyn Any> = object.clone();
+                object.downcast::<T>().map_err(|_| format!("Expected a different native object but got {}", type_name))
+            },
+            other => Err(expected("native object", other)),
+        }
+    }
+}
+
+impl IntoValue for LiteralValue {
+    fn into_value(self) -> LiteralValue {
+        self
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Number(self)
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Number(self as f64)
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::from_bool(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::StringValue(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::StringValue(self.to_string())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Null
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> LiteralValue {
+        match self {
+            Some(value) => value.into_value(),
+            None => LiteralValue::Null,
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> LiteralValue {
+        let items = self.into_iter().map(IntoValue::into_value).collect();
+        LiteralValue::List(Rc::new(RefCell::new(items)))
+    }
+}
+
+impl<T: NativeObject> IntoValue for Rc<T> {
+    fn into_value(self) -> LiteralValue {
+        LiteralValue::Native(self)
+    }
+}
+
+impl<T: IntoValue> IntoResult for T {
+    fn into_result(self) -> Result<LiteralValue, String> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue> IntoResult for Result<T, String> {
+    fn into_result(self) -> Result<LiteralValue, String> {
+        self.map(IntoValue::into_value)
+    }
+}
+
+impl IntoArgs for Vec<LiteralValue> {
+    fn into_args(self) -> Vec<LiteralValue> {
+        self
+    }
+}
+
+// Implements IntoArgs and RegisterFn for tuples and closures with the given argument types
+macro_rules! impl_arguments {
+    ($($arg:ident),*) => {
+        impl<$($arg: IntoValue),*> IntoArgs for ($($arg,)*) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<LiteralValue> {
+                let ($($arg,)*) = self;
+                vec![$($arg.into_value()),*]
+            }
+        }
+
+        impl<F, R, $($arg: FromValue),*> RegisterFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoResult,
+        {
+            #[allow(non_snake_case, unused_variables, unused_mut)]
+            fn into_callable(self, name: &str) -> LiteralValue {
+                let arity = <[&str]>::len(&[$(stringify!($arg)),*]);
+                let params: Vec<String> = (1..=arity).map(|index| format!("arg{}", index)).collect();
+
+                let fn_name = name.to_string();
+                let callable = move |args: &Vec<LiteralValue>| {
+                    let mut args = args.iter().enumerate();
+                    $(
+                        let (index, value) = args.next().expect("Arity is checked before calling");
+                        let $arg = $arg::from_value(value)
+                            .map_err(|msg| format!("{}, argument {} of {}", msg, index + 1, fn_name))?;
+                    )*
+                    let value = self($($arg),*).into_result()?;
+                    check_size(&value)?;
+
+                    Ok(value)
+                };
+
+                LiteralValue::Callable {
+                    name: name.to_string(),
+                    arity,
+                    params,
+                    fn_: Rc::new(callable),
+                }
+            }
+        }
+    };
+}
+
+impl_arguments!();
+impl_arguments!(A);
+impl_arguments!(A, B);
+impl_arguments!(A, B, C);
+impl_arguments!(A, B, C, D);
+impl_arguments!(A, B, C, D, E);
+impl_arguments!(A, B, C, D, E, G);
+
+// Calls a Zawa callable with Rust arguments
+pub fn call<T: FromValue>(callee: &LiteralValue, args: impl IntoArgs) -> Result<T, String> {
+    let args = args.into_args();
+
+    match callee {
+        LiteralValue::Callable { name, arity, .. } if *arity != args.len() => {
+            Err(format!("{} takes {} arguments but got {}", name, arity, args.len()))
+        },
+        callee => T::from_value(&call_stack::invoke(callee, args)?),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::parser::Parser;
+use crate::tokenizer::{TokenType, Tokenizer};
+
+// One token a line: the line it is on, its type and its lexeme
+pub fn dump_tokens(source: &str) -> Result<String, String> {
+    let tokens = Tokenizer::new(source).tokenize()?;
+
+    Ok(tokens
+        .iter()
+        .filter(|token| token.token_type != TokenType::Eof)
+        .map(|token| format!("{:>4} {:<12} {}\n", token.line_number, token.token_type.to_string(), token.lexeme))
+        .collect())
+}
+
+// One top level statement a line as an s-expression
+pub fn dump_ast(source: &str) -> Result<String, String> {
+    let tokens = Tokenizer::new(source).tokenize()?;
+    let stmts = Parser::new(tokens).parse()?;
+
+    Ok(stmts.iter().map(|stmt| format!("{}\n", stmt.to_string())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_and_statements_are_dumped() {
+        assert_eq!(dump_tokens("let a = 1;\necho a;").unwrap(), concat!(
+            "   1 Let          let\n",
+            "   1 Identifier   a\n",
+            "   1 Equal        =\n",
+            "   1 Number       1\n",
+            "   1 Semicolon    ;\n",
+            "   2 Echo         echo\n",
+            "   2 Identifier   a\n",
+            "   2 Semicolon    ;\n",
+        ));
+
+        let source = "fn f(n) { if (n > 1) { return n; } else return; } while (true) break;";
+        assert_eq!(
+            dump_ast(source).unwrap(),
+            "(fn f [n] (if (> var n 1) (block (return var n)) else (return)))\n(while true (break))\n"
+        );
+        assert!(dump_ast("let = 1;").is_err());
+    }
+}
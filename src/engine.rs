@@ -0,0 +1,401 @@
+use crate::call_stack;
+use crate::checker::Checker;
+use crate::convert::{self, FromValue, IntoArgs, IntoValue, RegisterFn};
+use crate::event_loop;
+use crate::expr::LiteralValue;
+use crate::interpreter::Interpreter;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::sandbox::{self, Capabilities, Limits};
+use crate::stmt::Stmt;
+use crate::tokenizer::Tokenizer;
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+// Runs Zawa source for a host application. Globals are kept between calls to
+// `eval`, the natives of the language are defined from the start.
+pub struct Engine {
+    interpreter: Interpreter,
+    // Budget every `eval` and `call_fn` starts with
+    limits: Limits,
+    // What the `args` native returns
+    args: Rc<RefCell<Vec<String>>>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::with_capabilities(Capabilities::all())
+    }
+
+    // Engine for untrusted scripts, natives outside of `capabilities` are not defined
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
+        let mut engine = Self {
+            interpreter: Interpreter::with_capabilities(capabilities),
+            limits: Limits::default(),
+            args: Rc::new(RefCell::new(Vec::new())),
+        };
+
+        let args = engine.args.clone();
+        engine.register_fn("args", move || args.borrow().clone());
+        engine
+    }
+
+    // Arguments of the script, `args()` gives them as a list of strings
+    pub fn set_args(&mut self, args: Vec<String>) {
+        *self.args.borrow_mut() = args;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    // Where print, println and echo write to, stdout by default
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.interpreter.streams.set_output(Box::new(output));
+    }
+
+    // Where read and readln read from, stdin by default
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.interpreter.streams.set_input(Box::new(input));
+    }
+
+    // Limit on nested calls for every engine on the current thread
+    pub fn set_max_depth(max_depth: usize) {
+        call_stack::set_max_depth(max_depth);
+    }
+
+    // Size in bytes of the native stack of the current thread, calls that would
+    // not fit on it raise a stack overflow error instead of crashing. Assumed to
+    // be 2MiB so engines are safe on any thread, a bigger one allows deeper calls.
+    pub fn set_stack_size(stack_size: usize) {
+        call_stack::set_stack_size(stack_size);
+    }
+
+    fn parse(&self, source: &str) -> Result<Vec<Stmt>, String> {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = tokenizer.tokenize()?;
+
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse()?;
+
+        let mut resolver = Resolver::new(&self.interpreter.environment.borrow());
+        resolver.resolve(&stmts)?;
+
+        Ok(stmts)
+    }
+
+    // Runs `source`, the value is the one of its last statement when that is an
+    // expression statement and null otherwise. Uncaught errors list the calls they were raised in.
+    pub fn eval(&mut self, source: &str) -> Result<LiteralValue, String> {
+        let stmts = self.parse(source)?;
+        self.run(stmts)
+    }
+
+    // Names and lines of the `test` blocks in `source`, in the order they are declared
+    pub fn tests(&self, source: &str) -> Result<Vec<(String, usize)>, String> {
+        let stmts = self.parse(source)?;
+
+        Ok(stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Test { keyword, name, body: _ } => Some((name.clone(), keyword.line_number)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    // Runs the rest of `source` and then the body of the test `name`, other tests are skipped
+    pub fn run_test(&mut self, source: &str, name: &str) -> Result<(), String> {
+        let (tests, mut stmts): (Vec<Stmt>, Vec<Stmt>) = self
+            .parse(source)?
+            .into_iter()
+            .partition(|stmt| matches!(stmt, Stmt::Test { .. }));
+
+        let body = tests.into_iter().find_map(|test| match test {
+            Stmt::Test { keyword: _, name: test_name, body } if test_name == name => Some(body),
+            _ => None,
+        });
+        match body {
+            Some(body) => stmts.push(*body),
+            None => return Err(format!("There is no test named {}", name)),
+        }
+
+        self.run(stmts).map(|_| ())
+    }
+
+    fn run(&mut self, mut stmts: Vec<Stmt>) -> Result<LiteralValue, String> {
+        let _running = sandbox::start(&self.limits);
+        let last = match stmts.last() {
+            Some(Stmt::Expression { .. }) => stmts.pop(),
+            _ => None,
+        };
+
+        // Tasks that were started but never awaited still run once the script has finished
+        let result = self.interpreter.interpret(stmts.iter().collect())
+            .and_then(|_| match last {
+                Some(Stmt::Expression { expression }) => expression.evaluate(self.interpreter.environment.clone()),
+                _ => Ok(LiteralValue::Null),
+            })
+            .and_then(|value| event_loop::run().map(|_| value));
+        if result.is_err() {
+            event_loop::clear();
+        }
+
+        result.map_err(|msg| {
+            let trace = call_stack::take_trace();
+            if trace.is_empty() {
+                msg
+            } else {
+                format!("{}\n{}", msg, call_stack::backtrace(&trace))
+            }
+        })
+    }
+
+    // Finds the syntax errors of `source` without running or type checking it
+    pub fn validate(&self, source: &str) -> Result<(), String> {
+        self.parse(source).map(|_| ())
+    }
+
+    // Parses and type checks `source` without running it
+    pub fn check(&self, source: &str) -> Result<(), String> {
+        let stmts = self.parse(source)?;
+
+        let mut checker = Checker::new(&self.interpreter.environment.borrow());
+        checker.check(&stmts)
+    }
+
+    // Defines a native backed by a Rust closure, its arguments are converted from
+    // Zawa values and a wrong type is a runtime error
+    pub fn register_fn<Args>(&mut self, name: &str, function: impl RegisterFn<Args>) {
+        let callable = function.into_callable(name);
+        self.interpreter.environment.borrow_mut().define_const(name.to_string(), callable);
+    }
+
+    pub fn get<T: FromValue>(&self, name: &str) -> Result<T, String> {
+        match self.interpreter.environment.borrow().get(name) {
+            Some(value) => T::from_value(&value),
+            None => Err(format!("{} was not declared in this scope", name)),
+        }
+    }
+
+    // Every global and its value sorted by name, natives included
+    pub fn globals(&self) -> Vec<(String, LiteralValue)> {
+        let mut globals = self.interpreter.environment.borrow().values();
+        globals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        globals
+    }
+
+    pub fn set(&mut self, name: &str, value: impl IntoValue) -> Result<(), String> {
+        let mut environment = self.interpreter.environment.borrow_mut();
+        if environment.is_const(name) {
+            return Err(format!("Can not assign to the constant {}", name));
+        }
+
+        environment.define(name.to_string(), value.into_value());
+        Ok(())
+    }
+
+    // Calls the Zawa function bound to the global `name`
+    pub fn call_fn<T: FromValue>(&mut self, name: &str, args: impl IntoArgs) -> Result<T, String> {
+        let callee: LiteralValue = self.get(name)?;
+        let _running = sandbox::start(&self.limits);
+        let result = convert::call(&callee, args).and_then(|value| event_loop::run().map(|_| value));
+        if result.is_err() {
+            event_loop::clear();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streams::OutputBuffer;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    #[test]
+    fn eval_returns_the_last_expression() {
+        let mut engine = Engine::new();
+
+        assert_eq!(engine.eval("let a = 2; a * 3;").unwrap(), LiteralValue::Number(6.0));
+        assert_eq!(engine.eval("let b = a;").unwrap(), LiteralValue::Null);
+        assert!(engine.eval("a +").is_err());
+
+        assert!(engine.validate("a +").is_err());
+        assert!(engine.validate("missing();").is_ok());
+    }
+
+    #[test]
+    fn registered_functions_convert_arguments() {
+        let mut engine = Engine::new();
+        engine.register_fn("add", |a: f64, b: f64| a + b);
+        engine.register_fn("shout", |s: String| format!("{}!", s));
+        engine.register_fn("checked", |x: i64| if x < 0 { Err(String::from("negative")) } else { Ok(x) });
+
+        assert_eq!(engine.eval("add(1, 2);").unwrap(), LiteralValue::Number(3.0));
+        assert_eq!(engine.eval("shout(\"hi\");").unwrap(), LiteralValue::StringValue(String::from("hi!")));
+        assert_eq!(engine.eval("checked(2);").unwrap(), LiteralValue::Number(2.0));
+        assert!(engine.eval("checked(-1);").unwrap_err().contains("negative"));
+        assert!(engine.eval("add(1, \"2\");").unwrap_err().contains("Expected a Number but got String"));
+        assert!(engine.eval("let add = 1;").is_err());
+    }
+
+    #[test]
+    fn globals_and_calls_from_rust() {
+        let mut engine = Engine::new();
+        engine.set("limit", 10.0).unwrap();
+        engine.eval("fn scale(x) { return x * limit; } let names = [\"a\", \"b\"];").unwrap();
+
+        assert_eq!(engine.get::<Vec<String>>("names").unwrap(), vec!["a", "b"]);
+        assert_eq!(engine.call_fn::<f64>("scale", (2.0,)).unwrap(), 20.0);
+        assert!(engine.call_fn::<f64>("scale", ()).is_err());
+        assert!(engine.get::<f64>("missing").is_err());
+        assert!(engine.set("println", 1.0).is_err());
+
+        let globals = engine.globals();
+        assert!(globals.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(globals.contains(&(String::from("limit"), LiteralValue::Number(10.0))));
+        assert!(globals.iter().any(|(name, _)| name == "println"));
+    }
+
+    #[test]
+    fn tests_run_after_the_rest_of_the_script() {
+        let source = "
+            test \"adds\" { assert add(1, 2) == 3, \"1 + 2 should be 3\"; }
+            fn add(a, b) { return a + b; }
+            test \"fails\" { assert add(1, 1) == 3, \"1 + 1 is \" + string(add(1, 1)); }
+            test \"bare\" { assert false; }
+        ";
+
+        let tests = Engine::new().tests(source).unwrap();
+        assert_eq!(tests, vec![(String::from("adds"), 2), (String::from("fails"), 4), (String::from("bare"), 5)]);
+
+        assert!(Engine::new().run_test(source, "adds").is_ok());
+        assert_eq!(Engine::new().run_test(source, "fails").unwrap_err(), "line: 4, Assertion failed: 1 + 1 is 2");
+        assert_eq!(Engine::new().run_test(source, "bare").unwrap_err(), "line: 5, Assertion failed");
+        assert!(Engine::new().run_test(source, "missing").is_err());
+
+        assert!(Engine::new().eval(source).is_ok());
+        assert!(Engine::new().eval("if (true) { test \"nested\" { } }").is_err());
+    }
+
+    #[test]
+    fn args_are_given_to_the_script() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.eval("len(args());").unwrap(), LiteralValue::Number(0.0));
+
+        engine.set_args(vec![String::from("a"), String::from("--flag")]);
+        assert_eq!(engine.eval("string(args());").unwrap(), LiteralValue::StringValue(String::from("[\"a\", \"--flag\"]")));
+    }
+
+    #[test]
+    fn limits_end_scripts_with_distinct_errors() {
+        let mut engine = Engine::new();
+        engine.set_limits(Limits { max_steps: Some(1000), ..Limits::default() });
+        assert!(engine.eval("while (true) {}").unwrap_err().starts_with("Step limit exceeded"));
+        assert!(engine.eval("let i = 0; while (i < 10) { i = i + 1; }").is_ok());
+        assert!(engine.eval("while (true) { try { let a = 1; } catch (e) {} }").unwrap_err().starts_with("Step limit exceeded"));
+
+        engine.set_limits(Limits { timeout: Some(Duration::from_millis(20)), ..Limits::default() });
+        assert!(engine.eval("while (true) {}").unwrap_err().starts_with("Time limit exceeded"));
+        assert!(engine.eval("await sleep(10000);").unwrap_err().starts_with("Time limit exceeded"));
+
+        engine.set_limits(Limits { max_size: Some(8), ..Limits::default() });
+        assert!(engine.eval("let s = \"abcd\"; s = s + s; s = s + s;").unwrap_err().starts_with("Size limit exceeded"));
+        assert!(engine.eval("let l = []; while (true) { push(l, 1); }").unwrap_err().contains("Size limit exceeded"));
+        assert!(engine.eval("let m = {}; let i = 0; while (true) { m[i] = i; i = i + 1; }").unwrap_err().contains("Size limit exceeded"));
+
+        engine.set_limits(Limits { max_size: Some(100), ..Limits::default() });
+        assert!(engine.eval("string([\"aaaaaaaaaaaaaaaaaaaa\", \"aaaaaaaaaaaaaaaaaaaa\", \"aaaaaaaaaaaaaaaaaaaa\", \"aaaaaaaaaaaaaaaaaaaa\", \"aaaaaaaaaaaaaaaaaaaa\"]);").unwrap_err().contains("Size limit exceeded"));
+        assert!(engine.eval("string([1, 2, 3]);").is_ok());
+        engine.set_input(Cursor::new("a".repeat(150)));
+        assert!(engine.eval("readln();").unwrap_err().contains("Size limit exceeded"));
+        engine.set_input(Cursor::new(format!("{} short", "b".repeat(150))));
+        assert!(engine.eval("read();").unwrap_err().contains("Size limit exceeded"));
+        assert!(engine.eval("read_file(\"Cargo.lock\");").unwrap_err().contains("Size limit exceeded"));
+        engine.register_fn("repeat", |n: f64| "a".repeat(n as usize));
+        assert!(engine.eval("repeat(101);").unwrap_err().contains("Size limit exceeded"));
+        assert!(engine.eval("repeat(100);").is_ok());
+
+        engine.set_limits(Limits { max_size: Some(10), ..Limits::default() });
+        assert!(engine.eval("(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13);").unwrap_err().starts_with("Size limit exceeded, Tuple"));
+        assert!(engine.eval("(1, 2, 3);").is_ok());
+
+        engine.set_limits(Limits { max_depth: Some(10), ..Limits::default() });
+        assert!(engine.eval("fn f(n) { return 1 + f(n); } f(1);").unwrap_err().contains("Stack overflow, more than 10 nested calls"));
+
+        engine.set_limits(Limits::default());
+        assert!(engine.eval("let j = 0; while (j < 5000) { j = j + 1; }").is_ok());
+    }
+
+    #[test]
+    fn limit_errors_can_not_be_caught() {
+        let mut engine = Engine::new();
+        engine.set_limits(Limits { timeout: Some(Duration::from_millis(50)), ..Limits::default() });
+        let started = std::time::Instant::now();
+        assert!(engine.eval("while (true) { try { while (true) {} } catch (e) {} }").unwrap_err().starts_with("Time limit exceeded"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        engine.set_limits(Limits { max_steps: Some(1000), ..Limits::default() });
+        assert!(engine.eval("try { while (true) {} } catch (e) {} println(1);").unwrap_err().starts_with("Step limit exceeded"));
+
+        engine.set_limits(Limits { max_size: Some(10), ..Limits::default() });
+        assert!(engine.eval("let caught = false; try { let s = string([1, 2, 3, 4, 5]); } catch (e) { caught = true; } caught;").unwrap_err().starts_with("Size limit exceeded"));
+        assert!(engine.eval("fn* items() { try { yield [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]; } catch (e) { yield 0; } } for (item in items()) {}").unwrap_err().starts_with("Size limit exceeded"));
+
+        // A fresh budget for every eval
+        assert!(engine.eval("try { nope; } catch (e) {} 1;").is_ok());
+    }
+
+    #[test]
+    fn deep_recursion_on_an_ordinary_thread_is_an_error() {
+        std::thread::spawn(|| {
+            let mut engine = Engine::new();
+            engine.eval("fn deep(n) { if (n == 0) { return 0; } return 1 + deep(n - 1); }").unwrap();
+
+            assert!(engine.eval("deep(4000);").unwrap_err().contains("Stack overflow"));
+            assert!(engine.call_fn::<f64>("deep", (4000.0,)).unwrap_err().contains("Stack overflow"));
+            assert_eq!(engine.eval("deep(10);").unwrap(), LiteralValue::Number(10.0));
+        }).join().unwrap();
+    }
+
+    #[test]
+    fn capabilities_leave_out_natives() {
+        let mut engine = Engine::with_capabilities(Capabilities { time: true, ..Capabilities::none() });
+
+        assert!(engine.eval("exit(1);").unwrap_err().contains("exit was not declared"));
+        assert!(engine.eval("readln();").unwrap_err().contains("readln was not declared"));
+        assert!(engine.eval("read_file(\"Cargo.toml\");").unwrap_err().contains("read_file was not declared"));
+        assert!(engine.eval("time();").is_ok());
+    }
+
+    #[test]
+    fn output_and_input_go_through_the_streams() {
+        let mut engine = Engine::new();
+        let output = OutputBuffer::new();
+        engine.set_output(output.clone());
+        engine.set_input(Cursor::new("Zawa 3\nsecond line\n"));
+
+        engine.eval("
+            let name = read();
+            let count = number(read());
+            fn greet() { echo \"in fn\"; }
+            greet();
+            print(name);
+            println(count + 1);
+            println(readln());
+        ").unwrap();
+
+        assert_eq!(output.contents(), "in fn\nZawa4\nsecond line\n");
+    }
+}
@@ -1,18 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::cell::RefCell;
 use crate::expr::LiteralValue;
+use crate::streams::Streams;
 use std::rc::Rc;
 
 pub struct Environment {
     values: HashMap<String, LiteralValue>,
+    constants: HashSet<String>,
     pub enclosing: Option<Rc<RefCell<Environment>>>,
+    // Only set on the globals of an interpreter
+    pub streams: Option<Rc<Streams>>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Self {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
+            streams: None,
+        }
+    }
+
+    // Streams of the interpreter whose globals this scope is enclosed by
+    pub fn streams(&self) -> Rc<Streams> {
+        match (&self.streams, &self.enclosing) {
+            (Some(streams), _) => streams.clone(),
+            (None, Some(env)) => env.borrow().streams(),
+            (None, None) => Rc::new(Streams::stdio()),
         }
     }
 
@@ -20,6 +35,11 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    pub fn define_const(&mut self, name: String, value: LiteralValue) {
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
     pub fn define_top_level(&mut self, name: String, value: LiteralValue) {
         match &self.enclosing {
             None => self.define(name, value),
@@ -37,6 +57,27 @@ impl Environment {
         }
     }
 
+    pub fn is_const(&self, name: &str) -> bool {
+        match (self.values.contains_key(name), &self.enclosing) {
+            (true, _) => self.constants.contains(name),
+            (false, Some(env)) => env.borrow().is_const(name),
+            (false, None) => false
+        }
+    }
+
+    pub fn is_local_const(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    // Names declared in this scope and their values, enclosing scopes are left out
+    pub fn values(&self) -> Vec<(String, LiteralValue)> {
+        self.values.iter().map(|(name, value)| (name.clone(), value.clone())).collect()
+    }
+
+    pub fn constant_names(&self) -> Vec<String> {
+        self.constants.iter().cloned().collect()
+    }
+
     pub fn assign(&mut self, name: &str, value: LiteralValue) -> bool {
         let old_value = self.values.get(name);
 
@@ -61,4 +102,18 @@ mod tests {
     fn try_unit() {
         let _environment = Environment::new();
     }
+
+    #[test]
+    fn const_is_seen_through_enclosing() {
+        let global = Rc::new(RefCell::new(Environment::new()));
+        global.borrow_mut().define_const(String::from("a"), LiteralValue::Null);
+
+        let mut local = Environment::new();
+        local.enclosing = Some(global.clone());
+        assert!(local.is_const("a"));
+        assert!(!local.is_local_const("a"));
+
+        local.define(String::from("a"), LiteralValue::Null);
+        assert!(!local.is_const("a"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,308 @@
+use crate::call_stack;
+use crate::expr::LiteralValue;
+use crate::generator::{GeneratorState, Resumed};
+use crate::sandbox;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Work scheduled on the event loop: the body of an `async fn` call, a `sleep` timer,
+// a `gather` over other tasks or a callable passed to `spawn`
+pub struct Task {
+    pub name: String,
+    state: RefCell<TaskState>,
+}
+
+struct TaskState {
+    kind: TaskKind,
+    result: Option<Result<LiteralValue, String>>,
+    // Tasks that are resumed with the result once this one has finished
+    waiters: Vec<Rc<Task>>,
+}
+
+enum TaskKind {
+    // None while the body is running
+    Coroutine(Option<Box<GeneratorState>>),
+    Timer,
+    Gather(Vec<LiteralValue>),
+    // None once the callable has been called
+    Call(Option<LiteralValue>),
+}
+
+// Tasks only run while the loop is driven, either by an `await` outside of an
+// `async fn` or once the script has finished
+struct EventLoop {
+    ready: VecDeque<(Rc<Task>, Result<LiteralValue, String>)>,
+    // Kept in the order they were started so timers with the same deadline fire in order
+    timers: Vec<(Instant, Rc<Task>)>,
+    running: bool,
+}
+
+enum Event {
+    Step(Rc<Task>, Result<LiteralValue, String>),
+    Timer(Rc<Task>),
+    // Waited until the time limit of the sandbox ran out
+    TimedOut,
+}
+
+thread_local! {
+    static EVENT_LOOP: RefCell<EventLoop> = const {
+        RefCell::new(EventLoop {
+            ready: VecDeque::new(),
+            timers: Vec::new(),
+            running: false,
+        })
+    };
+}
+
+impl EventLoop {
+    // Expired timers go first, the loop only sleeps when nothing else is ready
+    fn next(&mut self) -> Option<Event> {
+        let now = Instant::now();
+        let earliest = self.timers
+            .iter()
+            .enumerate()
+            .min_by_key(|(index, (deadline, _))| (*deadline, *index))
+            .map(|(index, (deadline, _))| (index, *deadline));
+
+        if let Some((index, deadline)) = earliest
+            && deadline <= now {
+            return Some(Event::Timer(self.timers.remove(index).1));
+        }
+        if let Some((task, sent)) = self.ready.pop_front() {
+            return Some(Event::Step(task, sent));
+        }
+
+        let (index, deadline) = earliest?;
+        let wait = deadline.saturating_duration_since(now);
+        if let Some(time_left) = sandbox::time_left()
+            && time_left < wait {
+            std::thread::sleep(time_left);
+            return Some(Event::TimedOut);
+        }
+
+        std::thread::sleep(wait);
+        Some(Event::Timer(self.timers.remove(index).1))
+    }
+}
+
+impl Task {
+    fn new(name: String, kind: TaskKind) -> Rc<Task> {
+        Rc::new(Task {
+            name,
+            state: RefCell::new(TaskState { kind, result: None, waiters: Vec::new() }),
+        })
+    }
+
+    // Task running the body of an `async fn` call, it starts once the loop runs
+    pub fn coroutine(body: GeneratorState) -> Rc<Task> {
+        let task = Task::new(body.name.clone(), TaskKind::Coroutine(Some(Box::new(body))));
+        schedule(task.clone(), Ok(LiteralValue::Null));
+        task
+    }
+
+    // Fails for durations too long to be a deadline, infinity included
+    pub fn sleep(milliseconds: f64) -> Result<Rc<Task>, String> {
+        let deadline = Duration::try_from_secs_f64(milliseconds / 1000.0)
+            .ok()
+            .and_then(|duration| Instant::now().checked_add(duration))
+            .ok_or_else(|| format!("Sleep duration too large, {} ms", milliseconds))?;
+
+        let task = Task::new(String::from("sleep"), TaskKind::Timer);
+        EVENT_LOOP.with(|event_loop| event_loop.borrow_mut().timers.push((deadline, task.clone())));
+        Ok(task)
+    }
+
+    // Calls `callable` on the loop, a task it returns is waited for
+    pub fn spawn(callable: LiteralValue) -> Rc<Task> {
+        let name = match &callable {
+            LiteralValue::Callable { name, .. } => name.clone(),
+            other => other.to_type().to_string(),
+        };
+
+        let task = Task::new(name, TaskKind::Call(Some(callable)));
+        schedule(task.clone(), Ok(LiteralValue::Null));
+        task
+    }
+
+    // Finishes with the list of results once every task in `items` has finished,
+    // items that are not tasks are taken as they are
+    pub fn gather(items: Vec<LiteralValue>) -> Rc<Task> {
+        let task = Task::new(String::from("gather"), TaskKind::Gather(items.clone()));
+
+        for item in &items {
+            if let LiteralValue::Task(item) = item {
+                let mut state = item.state.borrow_mut();
+                if state.result.is_none() {
+                    state.waiters.push(task.clone());
+                }
+            }
+        }
+
+        schedule(task.clone(), Ok(LiteralValue::Null));
+        task
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.borrow().result.is_some()
+    }
+
+    pub fn result(&self) -> Option<Result<LiteralValue, String>> {
+        self.state.borrow().result.clone()
+    }
+}
+
+fn schedule(task: Rc<Task>, sent: Result<LiteralValue, String>) {
+    EVENT_LOOP.with(|event_loop| event_loop.borrow_mut().ready.push_back((task, sent)));
+}
+
+// Resumes `task` once `value` has finished, right away when it is not a task
+fn wait_for(task: &Rc<Task>, value: LiteralValue) {
+    match value {
+        LiteralValue::Task(awaited) => {
+            let result = awaited.result();
+            match result {
+                Some(result) => schedule(task.clone(), result),
+                None => awaited.state.borrow_mut().waiters.push(task.clone()),
+            }
+        },
+        value => schedule(task.clone(), Ok(value)),
+    }
+}
+
+// A failed task that nobody waits for stops the loop with its error
+fn finish(task: &Rc<Task>, result: Result<LiteralValue, String>) -> Result<(), String> {
+    let waiters = {
+        let mut state = task.state.borrow_mut();
+        state.result = Some(result.clone());
+        std::mem::take(&mut state.waiters)
+    };
+
+    if waiters.is_empty() {
+        return result.map(|_| ());
+    }
+    for waiter in waiters {
+        schedule(waiter, result.clone());
+    }
+
+    Ok(())
+}
+
+fn step(task: &Rc<Task>, sent: Result<LiteralValue, String>) -> Result<(), String> {
+    // What to run is taken out so the task can be borrowed while it runs
+    let kind = {
+        let mut state = task.state.borrow_mut();
+        if state.result.is_some() {
+            return Ok(());
+        }
+
+        match &mut state.kind {
+            TaskKind::Coroutine(body) => TaskKind::Coroutine(body.take()),
+            TaskKind::Timer => return Ok(()),
+            TaskKind::Gather(items) => TaskKind::Gather(items.clone()),
+            TaskKind::Call(callable) => TaskKind::Call(callable.take()),
+        }
+    };
+
+    match kind {
+        TaskKind::Coroutine(Some(mut body)) => {
+            let resumed = body.resume(sent);
+            task.state.borrow_mut().kind = TaskKind::Coroutine(Some(body));
+
+            match resumed {
+                Ok(Resumed::Suspended(value)) => {
+                    wait_for(task, value);
+                    Ok(())
+                },
+                Ok(Resumed::Finished(value)) => finish(task, Ok(value)),
+                Err(msg) => finish(task, Err(msg)),
+            }
+        },
+        TaskKind::Coroutine(None) => Err(format!("Task {} is already running", task.name)),
+        TaskKind::Gather(items) => match gathered(&items) {
+            Some(result) => finish(task, result),
+            None => Ok(()),
+        },
+        TaskKind::Call(Some(callable)) => match call_stack::invoke(&callable, Vec::new()) {
+            Ok(value) => {
+                wait_for(task, value);
+                Ok(())
+            },
+            Err(msg) => finish(task, Err(msg)),
+        },
+        // Woken by the task the callable returned
+        TaskKind::Call(None) => finish(task, sent),
+        TaskKind::Timer => Ok(()),
+    }
+}
+
+fn gathered(items: &[LiteralValue]) -> Option<Result<LiteralValue, String>> {
+    let mut values = Vec::new();
+
+    for item in items {
+        match item {
+            LiteralValue::Task(task) => match task.result()? {
+                Ok(value) => values.push(value),
+                Err(msg) => return Some(Err(msg)),
+            },
+            other => values.push(other.clone()),
+        }
+    }
+
+    Some(Ok(LiteralValue::List(Rc::new(RefCell::new(values)))))
+}
+
+fn run_until(done: impl Fn() -> bool) -> Result<(), String> {
+    while !done() {
+        sandbox::step()?;
+        let event = EVENT_LOOP.with(|event_loop| event_loop.borrow_mut().next());
+
+        match event {
+            Some(Event::Step(task, sent)) => step(&task, sent)?,
+            Some(Event::Timer(task)) => finish(&task, Ok(LiteralValue::Null))?,
+            Some(Event::TimedOut) => sandbox::check_deadline()?,
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+// Drives the loop with `body`, tasks can not drive it themselves
+fn driving(body: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    let already_running = EVENT_LOOP.with(|event_loop| {
+        std::mem::replace(&mut event_loop.borrow_mut().running, true)
+    });
+    if already_running {
+        return Err(String::from("The event loop is already running"));
+    }
+
+    let result = body();
+    EVENT_LOOP.with(|event_loop| event_loop.borrow_mut().running = false);
+    result
+}
+
+// Runs the loop until `task` has finished, used by `await` outside of an `async fn`
+pub fn block_on(task: &Rc<Task>, line_number: usize) -> Result<LiteralValue, String> {
+    driving(|| run_until(|| task.is_done()))?;
+
+    match task.result() {
+        Some(result) => result,
+        None => Err(format!("line: {}, Task {} can never finish", line_number, task.name)),
+    }
+}
+
+// Runs every scheduled task and timer, called once the script has finished
+pub fn run() -> Result<(), String> {
+    driving(|| run_until(|| false))
+}
+
+// Drops the work left behind by a script that failed
+pub fn clear() {
+    EVENT_LOOP.with(|event_loop| {
+        let mut event_loop = event_loop.borrow_mut();
+        event_loop.ready.clear();
+        event_loop.timers.clear();
+    });
+}
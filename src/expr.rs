@@ -11,10 +11,39 @@ Binary operator Star cannot be applied for operands True, StringValue("a")
 
 use crate::tokenizer::{Token, TokenType};
 use crate::tokenizer;
+use crate::class::{ClassValue, InstanceValue, TraitValue};
+use crate::call_stack;
 use crate::environment::Environment;
+use crate::event_loop;
+use crate::generator::{self, GeneratorState};
 use crate::interpreter::Interpreter;
+use crate::native_object::{self, NativeObject};
+use crate::ordered_map::OrderedMap;
+use crate::pattern::Pattern;
+use crate::sandbox;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
+
+thread_local! {
+    // Lists, maps and instances being printed, one that contains itself is printed as `...` inside
+    static PRINTING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+// Prints a collection at `address` with `print`, or `repeat` when it is already being printed further out
+fn print_once(address: usize, repeat: &str, print: impl FnOnce() -> Result<String, String>) -> Result<String, String> {
+    if PRINTING.with(|printing| printing.borrow().contains(&address)) {
+        return Ok(repeat.to_string());
+    }
+
+    PRINTING.with(|printing| printing.borrow_mut().push(address));
+    let text = print();
+    PRINTING.with(|printing| printing.borrow_mut().pop());
+
+    text
+}
+
+pub type NativeFn = Rc<dyn Fn(&Vec<LiteralValue>) -> Result<LiteralValue, String>>;
 
 #[derive(Clone)]
 pub enum LiteralValue {
@@ -26,11 +55,84 @@ pub enum LiteralValue {
     Callable { 
         name: String,
         arity: usize,
-        fn_: Rc<dyn Fn(&Vec<LiteralValue>) -> LiteralValue>,
+        params: Vec<String>,
+        fn_: NativeFn,
     },
+    List(Rc<RefCell<Vec<LiteralValue>>>),
+    Map(Rc<RefCell<OrderedMap>>),
+    Tuple(Rc<Vec<LiteralValue>>),
+    Enum(Rc<EnumType>),
+    Variant(Rc<VariantValue>),
+    Class(Rc<ClassValue>),
+    Trait(Rc<TraitValue>),
+    Instance(Rc<InstanceValue>),
+    Generator(Rc<RefCell<GeneratorState>>),
+    Task(Rc<event_loop::Task>),
+    // Object defined by the host application
+    Native(Rc<dyn NativeObject>),
 }
 use LiteralValue::*;
 
+pub struct EnumType {
+    pub name: String,
+    // Variants declared without parentheses have no field list
+    pub variants: Vec<(String, Option<Vec<String>>)>,
+}
+
+#[derive(PartialEq)]
+pub struct VariantValue {
+    pub enum_name: String,
+    pub tag: String,
+    pub field_names: Vec<String>,
+    pub values: Vec<LiteralValue>,
+}
+
+impl EnumType {
+    pub fn from_declaration(name: &Token, variants: &[(Token, Option<Vec<Token>>)]) -> Self {
+        Self {
+            name: name.lexeme.clone(),
+            variants: variants.iter().map(|(tag, fields)| (
+                tag.lexeme.clone(),
+                fields.as_ref().map(|fields| fields.iter().map(|field| field.lexeme.clone()).collect())
+            )).collect(),
+        }
+    }
+
+    // Unit variants are values, variants with fields are constructor callables
+    pub fn variant(enum_type: &Rc<EnumType>, tag: &str) -> Option<LiteralValue> {
+        let (_, fields) = enum_type.variants.iter().find(|(name, _)| name == tag)?;
+
+        let fields = match fields {
+            None => return Some(Variant(Rc::new(VariantValue {
+                enum_name: enum_type.name.clone(),
+                tag: tag.to_string(),
+                field_names: Vec::new(),
+                values: Vec::new(),
+            }))),
+            Some(fields) => fields.clone(),
+        };
+
+        let enum_name = enum_type.name.clone();
+        let tag_name = tag.to_string();
+        let field_names = fields.clone();
+        let constructor = move |args: &Vec<LiteralValue>| {
+            Ok(Variant(Rc::new(VariantValue {
+                enum_name: enum_name.clone(),
+                tag: tag_name.clone(),
+                field_names: field_names.clone(),
+                values: args.clone(),
+            })))
+        };
+
+        Some(Callable {
+            name: format!("{}.{}", enum_type.name, tag),
+            arity: fields.len(),
+            params: fields,
+            fn_: Rc::new(constructor),
+        })
+    }
+}
+
 impl std::fmt::Debug for LiteralValue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.to_string())
@@ -45,23 +147,68 @@ impl PartialEq for LiteralValue {
                 Callable {
                     name,
                     arity,
-                    fn_: _
+                    ..
                 },
                 Callable {
                     name: name2,
                     arity: arity2,
-                    fn_: _
+                    ..
                 }
             ) => name == name2 && arity == arity2,
             (StringValue(s1), StringValue(s2)) => s1 == s2,
             (True, True) => true,
             (False, False) => true,
             (Null, Null) => true,
+            (List(l1), List(l2)) => Rc::ptr_eq(l1, l2) || *l1.borrow() == *l2.borrow(),
+            (Tuple(t1), Tuple(t2)) => t1 == t2,
+            (Enum(e1), Enum(e2)) => Rc::ptr_eq(e1, e2),
+            (Variant(v1), Variant(v2)) => v1 == v2,
+            (Class(c1), Class(c2)) => Rc::ptr_eq(c1, c2),
+            (Trait(t1), Trait(t2)) => Rc::ptr_eq(t1, t2),
+            (Instance(i1), Instance(i2)) => Rc::ptr_eq(i1, i2),
+            (Generator(g1), Generator(g2)) => Rc::ptr_eq(g1, g2),
+            (Task(t1), Task(t2)) => Rc::ptr_eq(t1, t2),
+            (Native(n1), Native(n2)) => Rc::ptr_eq(n1, n2),
+            (Map(m1), Map(m2)) => {
+                if Rc::ptr_eq(m1, m2) {
+                    return true;
+                }
+
+                let (m1, m2) = (m1.borrow(), m2.borrow());
+                m1.len() == m2.len() && m1.iter().all(|(key, value)| m2.get(key) == Some(value))
+            },
             _ => false,
         }
     }
 }
 
+impl Eq for LiteralValue {}
+
+// Only values accepted by `is_hashable` are ever used as map keys,
+// every other variant just hashes its discriminant
+impl Hash for LiteralValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Number(x) => {
+                let x = if *x == 0.0 { 0.0 } else { *x };
+                x.to_bits().hash(state);
+            },
+            StringValue(s) => s.hash(state),
+            Tuple(items) => items.hash(state),
+            Variant(variant) => {
+                variant.enum_name.hash(state);
+                variant.tag.hash(state);
+                variant.values.hash(state);
+            },
+            // Instances are compared by identity, so they hash by address
+            Instance(instance) => Rc::as_ptr(instance).hash(state),
+            Native(object) => Rc::as_ptr(object).cast::<()>().hash(state),
+            _ => (),
+        }
+    }
+}
+
 fn unwrap_as_f64(literal: Option<tokenizer::LiteralValue>) -> f64 {
     match literal {
         Some(tokenizer::LiteralValue::FValue(x)) => x as f64,
@@ -76,15 +223,144 @@ fn unwrap_as_string(literal: Option<tokenizer::LiteralValue>) -> String {
     }
 }
 
+// Dispatches a binary operator to a special method of the left operand, or of the
+// right one with the comparison flipped, so `1 < money` calls `money.__gt__(1)`
+fn overloaded_binary(left: &LiteralValue, operator: &Token, right: &LiteralValue) -> Option<Result<LiteralValue, String>> {
+    let (method, reflected) = match operator.token_type {
+        TokenType::Plus => ("__add__", None),
+        TokenType::Minus => ("__sub__", None),
+        TokenType::Star => ("__mul__", None),
+        TokenType::Slash => ("__div__", None),
+        TokenType::EqualEqual | TokenType::BangEqual => ("__eq__", Some("__eq__")),
+        TokenType::Less => ("__lt__", Some("__gt__")),
+        TokenType::LessEqual => ("__le__", Some("__ge__")),
+        TokenType::Greater => ("__gt__", Some("__lt__")),
+        TokenType::GreaterEqual => ("__ge__", Some("__le__")),
+        _ => return None,
+    };
+
+    let result = match (left, right) {
+        (Instance(instance), other) if instance.class.methods.contains_key(method) => {
+            InstanceValue::call_method(instance, method, vec![other.clone()], operator.line_number)
+        },
+        (other, Instance(instance)) => {
+            InstanceValue::call_method(instance, reflected?, vec![other.clone()], operator.line_number)
+        },
+        _ => None,
+    }?;
+
+    // `!=` is the negation of `__eq__`
+    if operator.token_type == TokenType::BangEqual {
+        Some(result.map(|value| value.is_falsy()))
+    } else {
+        Some(result)
+    }
+}
+
+fn list_index(index: f64, len: usize, bracket: &Token) -> Result<usize, String> {
+    if index.fract() != 0.0 || index < 0.0 || index >= len as f64 {
+        return Err(format!("line: {}, Index {} is out of bounds for length {}", bracket.line_number, index, len));
+    }
+
+    Ok(index as usize)
+}
+
 impl LiteralValue {
+    // Falls back to the default representation of instances whose __str__ fails
     pub fn to_string(&self) -> String {
+        self.try_to_string().unwrap_or_else(|_| self.stringify(false).unwrap_or_default())
+    }
+
+    // Like to_string, but strings are quoted so they can be told apart inside collections
+    pub fn to_repr(&self) -> String {
+        self.try_to_repr().unwrap_or_else(|_| self.repr(false).unwrap_or_default())
+    }
+
+    // Like to_string, but fails with the error of a __str__ method that fails
+    pub fn try_to_string(&self) -> Result<String, String> {
+        self.stringify(true)
+    }
+
+    pub fn try_to_repr(&self) -> Result<String, String> {
+        self.repr(true)
+    }
+
+    fn repr(&self, call_str: bool) -> Result<String, String> {
         match self {
+            StringValue(s) => Ok(format!("{:?}", s)),
+            other => other.stringify(call_str),
+        }
+    }
+
+    fn stringify(&self, call_str: bool) -> Result<String, String> {
+        let join = |items: &mut dyn Iterator<Item = &LiteralValue>| -> Result<String, String> {
+            Ok(items.map(|item| item.repr(call_str)).collect::<Result<Vec<String>, String>>()?.join(", "))
+        };
+
+        Ok(match self {
             Number(x) => x.to_string(),
             StringValue(s) => s.clone(),
             True => String::from("true"),
             False => String::from("false"),
             Null => String::from("null"),
-            Callable { name, arity , fn_: _ } => format!("{}/{}", name, arity)
+            Callable { name, arity, .. } => format!("{}/{}", name, arity),
+            List(items) => print_once(Rc::as_ptr(items) as usize, "[...]", || Ok(format!(
+                "[{}]",
+                join(&mut items.borrow().iter())?
+            )))?,
+            Map(map) => print_once(Rc::as_ptr(map) as usize, "{...}", || Ok(format!(
+                "{{{}}}",
+                map.borrow().iter().map(|(key, value)| Ok(format!("{}: {}", key.repr(call_str)?, value.repr(call_str)?))).collect::<Result<Vec<String>, String>>()?.join(", ")
+            )))?,
+            Tuple(items) if items.len() == 1 => format!("({},)", items[0].repr(call_str)?),
+            Tuple(items) => format!(
+                "({})",
+                join(&mut items.iter())?
+            ),
+            Enum(enum_type) => format!("enum {}", enum_type.name),
+            Variant(variant) if variant.field_names.is_empty() && variant.values.is_empty() => format!(
+                "{}.{}",
+                variant.enum_name,
+                variant.tag
+            ),
+            Variant(variant) => format!(
+                "{}.{}({})",
+                variant.enum_name,
+                variant.tag,
+                join(&mut variant.values.iter())?
+            ),
+            Class(class) => format!("class {}", class.name),
+            Generator(state) => format!("generator {}", state.try_borrow().map(|state| state.name.clone()).unwrap_or_default()),
+            Trait(trait_) => format!("trait {}", trait_.name),
+            Task(task) => format!("task {}", task.name),
+            Native(object) => object.to_string(),
+            Instance(instance) if call_str && instance.class.methods.contains_key("__str__") => {
+                match InstanceValue::call_method(instance, "__str__", Vec::new(), call_stack::line_number()) {
+                    Some(Ok(StringValue(s))) => s,
+                    Some(Ok(other)) => return Err(format!(
+                        "Method {}.__str__ should return a String but returned {}",
+                        instance.class.name, other.to_type()
+                    )),
+                    Some(Err(msg)) => return Err(msg),
+                    None => format!("{} {{}}", instance.class.name),
+                }
+            },
+            Instance(instance) if instance.fields.borrow().is_empty() => format!("{} {{}}", instance.class.name),
+            Instance(instance) => print_once(Rc::as_ptr(instance) as usize, &format!("{} {{...}}", instance.class.name), || Ok(format!(
+                "{} {{ {} }}",
+                instance.class.name,
+                instance.fields.borrow().iter().map(|(name, value)| Ok(format!("{}: {}", name.to_string(), value.repr(call_str)?))).collect::<Result<Vec<String>, String>>()?.join(", ")
+            )))?,
+        })
+    }
+
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Number(_) | StringValue(_) | True | False | Null => true,
+            Tuple(items) => items.iter().all(|item| item.is_hashable()),
+            Variant(variant) => variant.values.iter().all(|value| value.is_hashable()),
+            Instance(_) | Native(_) => true,
+            _ => false,
         }
     }
 
@@ -95,7 +371,18 @@ impl LiteralValue {
             True => "Boolean",
             False => "Boolean",
             Null => "Null",
-            Callable { name: _, arity: _ , fn_: _ } => "Callable"
+            Callable { .. } => "Callable",
+            List(_) => "List",
+            Map(_) => "Map",
+            Tuple(_) => "Tuple",
+            Enum(_) => "Enum",
+            Variant(variant) => &variant.enum_name,
+            Class(_) => "Class",
+            Trait(_) => "Trait",
+            Instance(instance) => &instance.class.name,
+            Generator(_) => "Generator",
+            Task(_) => "Task",
+            Native(object) => object.type_name(),
         }
     }
 
@@ -117,7 +404,11 @@ impl LiteralValue {
             Self::True => Self::False,
             Self::False => Self::True,
             Self::Null => Self::True,
-            Self::Callable { name: _, arity: _ , fn_: _ } => Self::False
+            Self::Callable { .. } => Self::False,
+            Self::List(items) => if items.borrow().is_empty() { Self::True } else { Self::False },
+            Self::Map(map) => if map.borrow().is_empty() { Self::True } else { Self::False },
+            Self::Tuple(items) => if items.is_empty() { Self::True } else { Self::False },
+            Self::Enum(_) | Self::Variant(_) | Self::Class(_) | Self::Trait(_) | Self::Instance(_) | Self::Generator(_) | Self::Task(_) | Self::Native(_) => Self::False,
         }
     }
 
@@ -128,7 +419,11 @@ impl LiteralValue {
             Self::True => Self::True,
             Self::False => Self::False,
             Self::Null => Self::False,
-            Self::Callable { name: _, arity: _, fn_: _ } => Self::True
+            Self::Callable { .. } => Self::True,
+            Self::List(items) => if items.borrow().is_empty() { Self::False } else { Self::True },
+            Self::Map(map) => if map.borrow().is_empty() { Self::False } else { Self::True },
+            Self::Tuple(items) => if items.is_empty() { Self::False } else { Self::True },
+            Self::Enum(_) | Self::Variant(_) | Self::Class(_) | Self::Trait(_) | Self::Instance(_) | Self::Generator(_) | Self::Task(_) | Self::Native(_) => Self::True,
         }
     }
 
@@ -143,7 +438,7 @@ use crate::stmt::Stmt;
 pub enum Expr {
     AnonFunction {
         paren: Token,
-        arguments: Vec<Token>,
+        arguments: Vec<Pattern>,
         body: Vec<Box<Stmt>>
     },
     Binary {
@@ -158,6 +453,7 @@ pub enum Expr {
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
+        keyword_arguments: Vec<(Token, Expr)>,
     },
     Literal {
         value: LiteralValue
@@ -178,6 +474,52 @@ pub enum Expr {
         name: Token,
         value: Box<Expr>
     },
+    List {
+        elements: Vec<Expr>
+    },
+    Tuple {
+        elements: Vec<Expr>
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>
+    },
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>
+    },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>
+    },
+    Match {
+        keyword: Token,
+        subject: Box<Expr>,
+        arms: Vec<MatchArm>
+    },
+    Await {
+        keyword: Token,
+        value: Box<Expr>
+    },
+}
+
+#[derive(Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
 }
 
 impl std::fmt::Debug for Expr {
@@ -191,9 +533,9 @@ impl Expr {
     pub fn to_string(&self) -> String {
         match self {
             Expr::AnonFunction {
-                paren,
+                paren: _,
                 arguments,
-                body
+                body: _
             } => format!(
                 "anon {}",
                 arguments.len()
@@ -208,11 +550,18 @@ impl Expr {
                 left.to_string(),
                 right.to_string()
             ),
-            Expr::Call { callee, paren: _, arguments} => format!(
-                "({} {:?})",
-                (*callee).to_string(),
-                arguments
-            ),
+            Expr::Call { callee, paren: _, arguments, keyword_arguments } => {
+                let keyword_str = keyword_arguments
+                    .iter()
+                    .map(|(name, value)| format!(" {}: {}", name.lexeme, value.to_string()))
+                    .collect::<String>();
+                format!(
+                    "({} {:?}{})",
+                    (*callee).to_string(),
+                    arguments,
+                    keyword_str
+                )
+            },
             Expr::Grouping { expression } => format!(
                 "(group {})",
                 expression.to_string()
@@ -240,6 +589,52 @@ impl Expr {
                 "{} = {}",
                 name.lexeme, value.to_string()
             ),
+            Expr::List { elements } => format!(
+                "(list {})",
+                elements.iter().map(|e| e.to_string()).collect::<Vec<String>>().join(" ")
+            ),
+            Expr::Tuple { elements } => format!(
+                "(tuple {})",
+                elements.iter().map(|e| e.to_string()).collect::<Vec<String>>().join(" ")
+            ),
+            Expr::Get { object, name } => format!(
+                "(get {} {})",
+                object.to_string(),
+                name.lexeme
+            ),
+            Expr::Map { brace: _, entries } => format!(
+                "(map {})",
+                entries.iter().map(|(k, v)| format!("{}: {}", k.to_string(), v.to_string())).collect::<Vec<String>>().join(" ")
+            ),
+            Expr::Index { object, bracket: _, index } => format!(
+                "(index {} {})",
+                object.to_string(),
+                index.to_string()
+            ),
+            Expr::Set { object, name, value } => format!(
+                "(set {} {} {})",
+                object.to_string(),
+                name.lexeme,
+                value.to_string()
+            ),
+            Expr::SetIndex { object, bracket: _, index, value } => format!(
+                "(index {} {}) = {}",
+                object.to_string(),
+                index.to_string(),
+                value.to_string()
+            ),
+            Expr::Match { keyword: _, subject, arms } => format!(
+                "(match {} {})",
+                subject.to_string(),
+                arms.iter().map(|arm| match &arm.guard {
+                    Some(guard) => format!("({} if {} => {})", arm.pattern, guard.to_string(), arm.body.to_string()),
+                    None => format!("({} => {})", arm.pattern, arm.body.to_string()),
+                }).collect::<Vec<String>>().join(" ")
+            ),
+            Expr::Await { keyword: _, value } => format!(
+                "(await {})",
+                value.to_string()
+            ),
         }
     }
 
@@ -247,12 +642,13 @@ impl Expr {
         match self {
             Expr::AnonFunction { paren, arguments, body } => {
                 let arity = arguments.len();
+                let params = arguments.iter().map(|p| p.param_name()).collect::<Vec<String>>();
                 let env = environment.clone();
 
                 let arguments = arguments
                     .iter()
-                    .map(|t| (*t).clone())
-                    .collect::<Vec<Token>>();
+                    .map(|p| (*p).clone())
+                    .collect::<Vec<Pattern>>();
 
                 let body = body
                     .iter()
@@ -263,31 +659,34 @@ impl Expr {
                 let fn_impl = move |args: &Vec<LiteralValue>| {
                     let mut anon_int = Interpreter::anon_function(env.clone());
                     for (i, arg) in args.iter().enumerate() {
-                        anon_int
-                            .environment
-                            .borrow_mut()
-                            .define(arguments[i].lexeme.clone(), (*arg).clone());
+                        let bindings = arguments[i].destructure(arg, anon_int.environment.clone(), paren_line)?;
+                        for (name, value) in bindings {
+                            anon_int.environment.borrow_mut().define(name, value);
+                        }
                     }
 
-                    for i in 0..(body.len()) {
-                        anon_int
-                            .interpret(vec![&body[i]])
-                            .expect(&format!("Evaluating failed inside anonymous function at line {}", paren_line));
+                    for stmt in body.iter() {
+                        anon_int.interpret(vec![stmt])?;
                         if let Some(value) = anon_int.specials.borrow().get("return") {
-                            return value;
+                            return Ok(value);
                         }
                     }
 
-                    LiteralValue::Null
+                    Ok(LiteralValue::Null)
                 };
 
                 Ok(Callable {
                     name: String::from("anon_function"),
                     arity: arity,
+                    params,
                     fn_: Rc::new(fn_impl)
                 })
             },
             Expr::Assign { name, value } => {
+                if environment.borrow().is_const(&name.lexeme) {
+                    return Err(format!("line: {}, Can not assign to constant {}", name.line_number, name.lexeme));
+                }
+
                 let new_value = (*value).evaluate(environment.clone())?;
                 let assign_success = environment.borrow_mut().assign(&name.lexeme, new_value.clone());
 
@@ -297,29 +696,14 @@ impl Expr {
                     Err(format!("{} was not declared in this scope", name.lexeme))
                 }
             },
-            Expr::Call { callee, paren, arguments } => {
-                let callable = (*callee).evaluate(environment.clone())?;
-                match callable {
-                    Callable { name, arity, fn_} => {
-                        if arguments.len() != arity {
-                            return Err(format!(
-                                "Callable {} expected {} arguments but {} were given",
-                                name,
-                                arity,
-                                arguments.len()
-                            ));
-                        }
-
-                        let mut arg_vals = Vec::new();
-                        for arg in arguments {
-                            let val = arg.evaluate(environment.clone())?;
-                            arg_vals.push(val);
-                        }
+            Expr::Call { callee, paren, arguments, keyword_arguments } => {
+                let callable = match (*callee).evaluate(environment.clone())? {
+                    Class(class) => ClassValue::constructor(&class),
+                    other => other,
+                };
+                let arg_vals = Expr::call_arguments(&callable, arguments, keyword_arguments, environment)?;
 
-                        Ok(fn_(&arg_vals))
-                    },
-                    other => Err(format!("{} is not callable", other.to_string()))
-                }
+                call_stack::call(&callable, arg_vals, paren.line_number)
             },
             Expr::Variable { name } => {
                 match environment.borrow().get(&name.lexeme) {
@@ -352,9 +736,198 @@ impl Expr {
                 }
             },
             Expr::Grouping { expression } => expression.evaluate(environment.clone()),
+            Expr::List { elements } => {
+                sandbox::check_size("List", elements.len())?;
+
+                let mut items = Vec::new();
+                for element in elements {
+                    items.push(element.evaluate(environment.clone())?);
+                }
+
+                Ok(List(Rc::new(RefCell::new(items))))
+            },
+            Expr::Tuple { elements } => {
+                sandbox::check_size("Tuple", elements.len())?;
+
+                let mut items = Vec::new();
+                for element in elements {
+                    items.push(element.evaluate(environment.clone())?);
+                }
+
+                Ok(Tuple(Rc::new(items)))
+            },
+            Expr::Get { object, name } => {
+                let object = object.evaluate(environment.clone())?;
+
+                match (&object, name.token_type) {
+                    (Tuple(items), TokenType::Number) => {
+                        match name.lexeme.parse::<usize>().ok().and_then(|i| items.get(i)) {
+                            Some(item) => Ok(item.clone()),
+                            None => Err(format!("line: {}, Tuple of length {} has no field {}", name.line_number, items.len(), name.lexeme)),
+                        }
+                    },
+                    (Enum(enum_type), TokenType::Identifier) => match EnumType::variant(enum_type, &name.lexeme) {
+                        Some(variant) => Ok(variant),
+                        None => Err(format!("line: {}, Enum {} has no variant {}", name.line_number, enum_type.name, name.lexeme)),
+                    },
+                    (Instance(instance), TokenType::Identifier) => match InstanceValue::get(instance, &name.lexeme) {
+                        Some(value) => Ok(value),
+                        None => Err(format!("line: {}, {} has no field or method {}", name.line_number, instance.class.name, name.lexeme)),
+                    },
+                    (Generator(state), TokenType::Identifier) if name.lexeme == "next" => {
+                        let state = state.clone();
+                        let next = move |_args: &Vec<LiteralValue>| {
+                            generator::resume(&state).map(|value| value.unwrap_or(Null))
+                        };
+                        Ok(Callable { name: String::from("next"), arity: 0, params: Vec::new(), fn_: Rc::new(next) })
+                    },
+                    (Generator(state), TokenType::Identifier) if name.lexeme == "done" => {
+                        Ok(LiteralValue::from_bool(state.try_borrow().map(|state| state.done).unwrap_or(false)))
+                    },
+                    (Task(task), TokenType::Identifier) if name.lexeme == "done" => Ok(LiteralValue::from_bool(task.is_done())),
+                    (Native(native), TokenType::Identifier) => match native_object::get(native, &name.lexeme) {
+                        Some(value) => Ok(value),
+                        None => Err(format!("line: {}, {} has no field or method {}", name.line_number, native.type_name(), name.lexeme)),
+                    },
+                    (Class(class), TokenType::Identifier) => match class.methods.get(&name.lexeme) {
+                        Some(method) => Ok(method.clone()),
+                        None => Err(format!("line: {}, Class {} has no method {}", name.line_number, class.name, name.lexeme)),
+                    },
+                    (Variant(variant), token_type) => {
+                        let index = match token_type {
+                            TokenType::Number => name.lexeme.parse::<usize>().ok(),
+                            _ => variant.field_names.iter().position(|field| *field == name.lexeme),
+                        };
+
+                        match index.and_then(|i| variant.values.get(i)) {
+                            Some(value) => Ok(value.clone()),
+                            None => Err(format!("line: {}, {}.{} has no field {}", name.line_number, variant.enum_name, variant.tag, name.lexeme)),
+                        }
+                    },
+                    (object, _) => Err(format!("line: {}, {} has no field {}", name.line_number, object.to_type(), name.lexeme)),
+                }
+            },
+            Expr::Map { brace, entries } => {
+                let mut map = OrderedMap::new();
+                for (key, value) in entries {
+                    let key = key.evaluate(environment.clone())?;
+                    if !key.is_hashable() {
+                        return Err(format!("line: {}, {} can not be used as a map key", brace.line_number, key.to_type()));
+                    }
+
+                    let value = value.evaluate(environment.clone())?;
+                    map.insert(key, value);
+                    sandbox::check_size("Map", map.len())?;
+                }
+
+                Ok(Map(Rc::new(RefCell::new(map))))
+            },
+            Expr::Index { object, bracket, index } => {
+                let object = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+
+                if let Instance(instance) = &object
+                    && let Some(result) = InstanceValue::call_method(instance, "__index__", vec![index.clone()], bracket.line_number) {
+                    return result;
+                }
+
+                match (&object, &index) {
+                    (List(items), Number(i)) => {
+                        let items = items.borrow();
+                        let i = list_index(*i, items.len(), bracket)?;
+                        Ok(items[i].clone())
+                    },
+                    (Tuple(items), Number(i)) => {
+                        let i = list_index(*i, items.len(), bracket)?;
+                        Ok(items[i].clone())
+                    },
+                    (StringValue(s), Number(i)) => {
+                        let i = list_index(*i, s.chars().count(), bracket)?;
+                        Ok(StringValue(s.chars().nth(i).unwrap().to_string()))
+                    },
+                    (Map(map), key) => Ok(map.borrow().get(key).cloned().unwrap_or(Null)),
+                    (object, index) => Err(format!("line: {}, {} can not be indexed with {}", bracket.line_number, object.to_type(), index.to_type())),
+                }
+            },
+            Expr::Set { object, name, value } => {
+                let object = object.evaluate(environment.clone())?;
+                let value = value.evaluate(environment.clone())?;
+
+                match &object {
+                    Instance(instance) => instance.set(&name.lexeme, value.clone()),
+                    Native(native) => native
+                        .set(&name.lexeme, value.clone())
+                        .map_err(|msg| format!("line: {}, {}", name.line_number, msg))?,
+                    object => return Err(format!("line: {}, Can not set field {} on {}", name.line_number, name.lexeme, object.to_type())),
+                }
+
+                Ok(value)
+            },
+            Expr::SetIndex { object, bracket, index, value } => {
+                let object = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                let value = value.evaluate(environment.clone())?;
+
+                match (&object, &index) {
+                    (List(items), Number(i)) => {
+                        let mut items = items.borrow_mut();
+                        let i = list_index(*i, items.len(), bracket)?;
+                        items[i] = value.clone();
+                    },
+                    (Map(map), key) => {
+                        if !key.is_hashable() {
+                            return Err(format!("line: {}, {} can not be used as a map key", bracket.line_number, key.to_type()));
+                        }
+                        let mut map = map.borrow_mut();
+                        map.insert(key.clone(), value.clone());
+                        sandbox::check_size("Map", map.len())
+                            .map_err(|msg| format!("line: {}, {}", bracket.line_number, msg))?;
+                    },
+                    (object, index) => return Err(format!("line: {}, {} does not support index assignment with {}", bracket.line_number, object.to_type(), index.to_type())),
+                }
+
+                Ok(value)
+            },
+            Expr::Match { keyword, subject, arms } => {
+                let value = subject.evaluate(environment.clone())?;
+
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    if !arm.pattern.matches(&value, environment.clone(), &mut bindings)? {
+                        continue;
+                    }
+
+                    let mut arm_environment = Environment::new();
+                    arm_environment.enclosing = Some(environment.clone());
+                    for (name, bound) in bindings {
+                        arm_environment.define(name, bound);
+                    }
+                    let arm_environment = Rc::new(RefCell::new(arm_environment));
+
+                    if let Some(guard) = &arm.guard
+                        && guard.evaluate(arm_environment.clone())?.is_truthy() != True {
+                        continue;
+                    }
+
+                    return arm.body.evaluate(arm_environment);
+                }
+
+                Err(format!("line: {}, No match arm matched value {}", keyword.line_number, value.to_repr()))
+            },
+            // Inside an `async fn` the task is suspended before this is reached,
+            // anywhere else the event loop runs until the task has finished
+            Expr::Await { keyword, value } => match value.evaluate(environment)? {
+                Task(task) => event_loop::block_on(&task, keyword.line_number),
+                value => Ok(value),
+            },
             Expr::Unary {operator, right} => {
                 let right = right.evaluate(environment.clone())?;
 
+                if let (Instance(instance), TokenType::Minus) = (&right, operator.token_type)
+                    && let Some(result) = InstanceValue::call_method(instance, "__neg__", Vec::new(), operator.line_number) {
+                    return result;
+                }
+
                 match (&right, operator.token_type) {
                     (Number(x), TokenType::Minus) => Ok(Number(-x)),
                     (_, TokenType::Minus) => Err(format!("Minus not implemented for {}", right.to_type())),
@@ -366,6 +939,10 @@ impl Expr {
                 let left = left.evaluate(environment.clone())?;
                 let right = right.evaluate(environment.clone())?;
 
+                if let Some(result) = overloaded_binary(&left, operator, &right) {
+                    return result;
+                }
+
                 match (&left, operator.token_type, &right) {
                     //expreimental
                     (Number(x), TokenType::Slash, Number(0.0)) => Err(format!("Binary operator Slash cannot be applied for operands {:?}, Number(0.0)", Number(*x))),
@@ -383,7 +960,10 @@ impl Expr {
                     (StringValue(s), operator, Number(x)) => Err(format!("Binary operator {} cannot be applied for operands {:?}, {:?}", operator, StringValue(String::from(s)), Number(*x))),
                     (Number(x), operator, StringValue(s)) => Err(format!("Binary operator {} cannot be applied for operands {:?}, {:?}", operator, Number(*x), StringValue(String::from(s)))),
 
-                    (StringValue(s1), TokenType::Plus, StringValue(s2)) => Ok(StringValue(format!("{}{}", s1, s2))),
+                    (StringValue(s1), TokenType::Plus, StringValue(s2)) => {
+                        sandbox::check_size("String", s1.len() + s2.len())?;
+                        Ok(StringValue(format!("{}{}", s1, s2)))
+                    },
 
                     (a, TokenType::BangEqual, b) => Ok(LiteralValue::from_bool(a != b)),
                     (a, TokenType::EqualEqual, b) => Ok(LiteralValue::from_bool(a == b)),
@@ -399,6 +979,59 @@ impl Expr {
         }
     }
 
+    // Evaluates the positional and keyword arguments of a call in parameter order
+    pub fn call_arguments(
+        callable: &LiteralValue,
+        arguments: &[Expr],
+        keyword_arguments: &[(Token, Expr)],
+        environment: Rc<RefCell<Environment>>
+    ) -> Result<Vec<LiteralValue>, String> {
+        let (name, arity, params) = match callable {
+            Callable { name, arity, params, .. } => (name, *arity, params),
+            other => return Err(format!("{} is not callable", other.to_string())),
+        };
+
+        let given = arguments.len() + keyword_arguments.len();
+        if given != arity {
+            return Err(format!(
+                "Callable {} expected {} arguments but {} were given",
+                name,
+                arity,
+                given
+            ));
+        }
+
+        let mut arg_vals: Vec<Option<LiteralValue>> = vec![None; arity];
+        for (i, arg) in arguments.iter().enumerate() {
+            arg_vals[i] = Some(arg.evaluate(environment.clone())?);
+        }
+
+        for (param_name, arg) in keyword_arguments {
+            let index = match params.iter().position(|p| *p == param_name.lexeme) {
+                Some(index) => index,
+                None => return Err(format!(
+                    "line: {}, Callable {} has no parameter named {}",
+                    param_name.line_number,
+                    name,
+                    param_name.lexeme
+                )),
+            };
+
+            if arg_vals[index].is_some() {
+                return Err(format!(
+                    "line: {}, Callable {} got multiple values for parameter {}",
+                    param_name.line_number,
+                    name,
+                    param_name.lexeme
+                ));
+            }
+
+            arg_vals[index] = Some(arg.evaluate(environment.clone())?);
+        }
+
+        Ok(arg_vals.into_iter().flatten().collect())
+    }
+
     #[allow(dead_code)]
     pub fn print(&self) {
         println!("{}", self.to_string());
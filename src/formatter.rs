@@ -0,0 +1,671 @@
+// Source formatter of `zawa fmt`. The parser only checks the source, the tokens are then
+// printed again following the grammar, so nothing the syntax tree leaves out is lost.
+
+use crate::parser::Parser;
+use crate::tokenizer::{Comment, Token, TokenType, Tokenizer};
+
+const INDENT: &str = "    ";
+
+// Source laid out with the canonical indentation, spacing and braces, comments are kept
+pub fn format_source(source: &str) -> Result<String, String> {
+    let mut tokenizer = Tokenizer::new(source);
+    let tokens = tokenizer.tokenize()?;
+    Parser::new(tokens.clone()).parse()?;
+
+    let mut formatter = Formatter::new(tokens, tokenizer.comments());
+    formatter.program();
+
+    Ok(formatter.out)
+}
+
+// Line a token starts on, strings can span lines and tokens carry the line they end on
+fn start_line(token: &Token) -> usize {
+    token.line_number - token.lexeme.matches('\n').count()
+}
+
+struct Formatter {
+    tokens: Vec<Token>,
+    comments: Vec<Comment>,
+    current: usize,
+    // First comment that is not written yet
+    comment: usize,
+    out: String,
+    indent: usize,
+    // What goes before the next word, it is only written with the word so a comment
+    // can still end the current line
+    space: bool,
+    newline: bool,
+    blank: bool,
+    // Source line of the last token or comment written
+    line: usize,
+}
+
+impl Formatter {
+    fn new(tokens: Vec<Token>, comments: Vec<Comment>) -> Self {
+        Self {
+            tokens,
+            comments,
+            current: 0,
+            comment: 0,
+            out: String::new(),
+            indent: 0,
+            space: false,
+            newline: false,
+            blank: false,
+            line: 0,
+        }
+    }
+
+    fn program(&mut self) {
+        while !self.check(TokenType::Eof) {
+            self.statement_start();
+            self.declaration();
+        }
+
+        self.comments_before();
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    // Every statement goes on its own line, an empty line between two statements is kept
+    fn statement_start(&mut self) {
+        self.line_break();
+        self.comments_before();
+        if start_line(&self.peek()) > self.line + 1 {
+            self.blank_line();
+        }
+    }
+
+    fn declaration(&mut self) {
+        match self.peek().token_type {
+            TokenType::Let => self.let_declaration(),
+            TokenType::Fn | TokenType::Async => self.function(),
+            TokenType::Class => self.class_declaration(),
+            TokenType::Enum => {
+                self.keyword_and_name();
+                self.space();
+                self.delimited(TokenType::RightBrace, true);
+            },
+            // `trait Name {` and `test "name" {`, test is a keyword only in front of a name
+            TokenType::Trait => self.named_block(),
+            TokenType::Identifier if self.peek().lexeme == "test" && self.check_next(TokenType::StringLit) => self.named_block(),
+            TokenType::LeftBrace => self.block(),
+            TokenType::If => self.if_statement(),
+            TokenType::While => {
+                self.token();
+                self.space();
+                self.condition();
+                self.body();
+            },
+            TokenType::For => self.for_statement(),
+            TokenType::Try => {
+                self.token();
+                self.space();
+                self.block();
+                self.space();
+                self.token();
+                self.space();
+                self.condition();
+                self.space();
+                self.block();
+            },
+            TokenType::Const | TokenType::Echo | TokenType::Return | TokenType::Yield | TokenType::Break | TokenType::Assert => {
+                self.token();
+                if !self.check(TokenType::Semicolon) {
+                    self.space();
+                    self.expression();
+                }
+                // Message of an assert
+                if self.check(TokenType::Comma) {
+                    self.token();
+                    self.space();
+                    self.expression();
+                }
+                self.token();
+            },
+            _ => {
+                self.expression();
+                self.token();
+            },
+        }
+    }
+
+    fn keyword_and_name(&mut self) {
+        self.token();
+        self.space();
+        self.token();
+    }
+
+    fn named_block(&mut self) {
+        self.keyword_and_name();
+        self.space();
+        self.block();
+    }
+
+    fn let_declaration(&mut self) {
+        self.token();
+        self.space();
+        self.expression();
+
+        if self.check(TokenType::Colon) {
+            self.token();
+            self.space();
+            self.token();
+        }
+        // Without an annotation the `=` is already written with the pattern
+        if self.check(TokenType::Equal) {
+            self.space();
+            self.token();
+            self.space();
+            self.expression();
+        }
+        self.token();
+    }
+
+    // Functions, methods and the signatures of traits
+    fn function(&mut self) {
+        if self.check(TokenType::Async) {
+            self.token();
+            self.space();
+        }
+        self.token();
+        if self.check(TokenType::Star) {
+            self.token();
+        }
+        self.space();
+        self.token();
+        self.delimited(TokenType::RightParen, false);
+
+        if self.check(TokenType::Arrow) {
+            self.space();
+            self.token();
+            self.space();
+            self.token();
+        }
+
+        if self.check(TokenType::Semicolon) {
+            self.token();
+        } else {
+            self.space();
+            self.block();
+        }
+    }
+
+    fn class_declaration(&mut self) {
+        self.keyword_and_name();
+
+        if self.check(TokenType::Impl) {
+            self.space();
+            self.keyword_and_name();
+            while self.check(TokenType::Comma) {
+                self.token();
+                self.space();
+                self.token();
+            }
+        }
+
+        self.space();
+        self.block();
+    }
+
+    fn block(&mut self) {
+        self.token();
+        self.indent += 1;
+
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::Eof) {
+            self.statement_start();
+            self.declaration();
+        }
+
+        self.comments_before();
+        self.indent -= 1;
+        // Empty blocks stay `{}`
+        if !self.out.ends_with('{') {
+            self.line_break();
+        }
+        self.token();
+    }
+
+    // `(expression)` after if, while, match and catch
+    fn condition(&mut self) {
+        self.token();
+        self.expression();
+        self.token();
+    }
+
+    // Body of a loop or a branch, statements that are not blocks stay on the same line.
+    // True when it was a block.
+    fn body(&mut self) -> bool {
+        let block = self.check(TokenType::LeftBrace);
+        self.space();
+        self.declaration();
+
+        block
+    }
+
+    fn if_statement(&mut self) {
+        self.token();
+        self.space();
+        self.condition();
+        let block = self.body();
+
+        if self.check(TokenType::Else) {
+            if block {
+                self.space();
+            } else {
+                self.line_break();
+            }
+            self.token();
+            self.body();
+        }
+    }
+
+    fn for_statement(&mut self) {
+        self.token();
+        self.space();
+        self.token();
+
+        // `for (pattern in iterable)` is written as one expression
+        let c_style = if self.check(TokenType::Let) {
+            self.let_declaration();
+            true
+        } else {
+            if !self.check(TokenType::Semicolon) {
+                self.expression();
+            }
+            let c_style = self.check(TokenType::Semicolon);
+            if c_style {
+                self.token();
+            }
+            c_style
+        };
+
+        if c_style {
+            if !self.check(TokenType::Semicolon) {
+                self.space();
+                self.expression();
+            }
+            self.token();
+            if !self.check(TokenType::RightParen) {
+                self.space();
+                self.expression();
+            }
+        }
+
+        self.token();
+        self.body();
+    }
+
+    // Tokens of an expression or a pattern up to what ends it, brackets in it included
+    fn expression(&mut self) {
+        // Whether the last token ends an operand, then a '-' is a subtraction,
+        // a '(' a call and a '[' an index
+        let mut operand = false;
+
+        loop {
+            match self.peek().token_type {
+                TokenType::Semicolon | TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace |
+                TokenType::Comma | TokenType::Colon | TokenType::FatArrow | TokenType::If | TokenType::Eof => break,
+                TokenType::LeftParen => self.delimited(TokenType::RightParen, false),
+                TokenType::LeftBracket => self.delimited(TokenType::RightBracket, !operand),
+                TokenType::LeftBrace => self.delimited(TokenType::RightBrace, true),
+                TokenType::Fn if self.check_next(TokenType::LeftParen) => {
+                    self.token();
+                    self.space();
+                    self.delimited(TokenType::RightParen, false);
+                    self.space();
+                    self.block();
+                },
+                TokenType::Match => {
+                    self.token();
+                    self.space();
+                    self.condition();
+                    self.space();
+                    self.match_arms();
+                },
+                TokenType::Dot => {
+                    self.token();
+                    self.token();
+                },
+                TokenType::Minus | TokenType::Bang if !operand => {
+                    self.token();
+                    continue;
+                },
+                TokenType::Await => {
+                    self.token();
+                    self.space();
+                    continue;
+                },
+                TokenType::DotDot | TokenType::DotDotEqual | TokenType::DotDotDot => {
+                    self.token();
+                    operand = false;
+                    continue;
+                },
+                TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash |
+                TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual |
+                TokenType::EqualEqual | TokenType::BangEqual | TokenType::And | TokenType::Or |
+                TokenType::Equal | TokenType::Pipe | TokenType::In => {
+                    self.space();
+                    self.token();
+                    self.space();
+                    operand = false;
+                    continue;
+                },
+                _ => {
+                    self.token();
+                },
+            }
+
+            operand = true;
+        }
+    }
+
+    // Items between brackets separated by commas, an item can be `key: value`. When the
+    // first item of a list or a map is on a new line in the source, each item gets its own
+    // line and a trailing comma, otherwise trailing commas are dropped.
+    fn delimited(&mut self, close: TokenType, allow_multiline: bool) {
+        let open = self.token();
+        if self.check(close) {
+            self.token();
+            return;
+        }
+
+        let multiline = allow_multiline && start_line(&self.peek()) > open.line_number;
+        // `{ key: value }`
+        let spaced = close == TokenType::RightBrace;
+        if multiline {
+            self.indent += 1;
+        }
+
+        let mut items = 0;
+        while !self.check(close) && !self.check(TokenType::Eof) {
+            if multiline {
+                self.line_break();
+            } else if spaced && items == 0 {
+                self.space();
+            }
+
+            self.expression();
+            if self.check(TokenType::Colon) {
+                self.token();
+                self.space();
+                self.expression();
+            }
+            items += 1;
+
+            if !self.check(TokenType::Comma) {
+                if multiline {
+                    self.word(",");
+                }
+                break;
+            }
+            // `(a,)` is a tuple, the comma can not go
+            if multiline || !self.check_next(close) || (close == TokenType::RightParen && items == 1) {
+                self.token();
+                self.space();
+            } else {
+                self.skip();
+            }
+        }
+
+        self.comments_before();
+        if multiline {
+            self.indent -= 1;
+            self.line_break();
+        } else {
+            self.space = spaced;
+        }
+        self.token();
+    }
+
+    // One arm a line, each with a trailing comma
+    fn match_arms(&mut self) {
+        self.token();
+        if self.check(TokenType::RightBrace) {
+            self.token();
+            return;
+        }
+        self.indent += 1;
+
+        while !self.check(TokenType::RightBrace) && !self.check(TokenType::Eof) {
+            self.line_break();
+            self.expression();
+            // Guard
+            if self.check(TokenType::If) {
+                self.space();
+                self.token();
+                self.space();
+                self.expression();
+            }
+            self.space();
+            self.token();
+            self.space();
+            self.expression();
+
+            if self.check(TokenType::Comma) {
+                self.token();
+            } else {
+                self.word(",");
+            }
+        }
+
+        self.comments_before();
+        self.indent -= 1;
+        self.line_break();
+        self.token();
+    }
+
+    fn peek(&self) -> Token {
+        self.tokens[self.current].clone()
+    }
+
+    fn check(&self, type_: TokenType) -> bool {
+        self.tokens[self.current].token_type == type_
+    }
+
+    fn check_next(&self, type_: TokenType) -> bool {
+        self.tokens.get(self.current + 1).is_some_and(|token| token.token_type == type_)
+    }
+
+    // Writes the next token after the comments that come before it
+    fn token(&mut self) -> Token {
+        self.comments_before();
+
+        let token = self.peek();
+        if token.token_type != TokenType::Eof {
+            self.current += 1;
+            self.word(&token.lexeme);
+            self.line = token.line_number;
+        }
+
+        token
+    }
+
+    // Leaves out the next token, the comments before it are still written
+    fn skip(&mut self) {
+        self.comments_before();
+        self.current += 1;
+    }
+
+    // A comment on the line of the last token stays at the end of that line,
+    // the others go on their own line
+    fn comments_before(&mut self) {
+        while let Some(comment) = self.comments.get(self.comment).filter(|comment| comment.token <= self.current).cloned() {
+            if !self.out.is_empty() && comment.line_number == self.line {
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+            } else {
+                if comment.line_number > self.line + 1 {
+                    self.blank_line();
+                } else {
+                    self.line_break();
+                }
+                self.word(&comment.text);
+            }
+
+            self.line_break();
+            self.line = comment.line_number;
+            self.comment += 1;
+        }
+    }
+
+    fn space(&mut self) {
+        self.space = true;
+    }
+
+    fn line_break(&mut self) {
+        if !self.out.is_empty() {
+            self.newline = true;
+        }
+    }
+
+    // Not at the start of the file or of a block
+    fn blank_line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('{') {
+            self.newline = true;
+            self.blank = true;
+        }
+    }
+
+    fn word(&mut self, text: &str) {
+        if self.newline {
+            self.out.push('\n');
+            if self.blank {
+                self.out.push('\n');
+            }
+            self.out.push_str(&INDENT.repeat(self.indent));
+        } else if self.space && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(text);
+        self.space = false;
+        self.newline = false;
+        self.blank = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_is_laid_out_canonically() {
+        let source = concat!(
+            "fn  add( x : number,y )->number{return x+y;}\n",
+            "let t=(1,);let u=[1,2,];let m={a:1,\"b\":-2,};\n",
+            "for(let i=0;i<3;i=i+1)println(i);\n",
+            "if(a>1){println(\"big\");}else if(a<0)println(\"neg\"); else {}\n",
+            "echo match(a){1|2=>\"small\",3..10=>\"mid\",n if n>100=>\"big\"};\n",
+            "let g=fn(a,[b,...rest]){return await add(a,b:b);};\n",
+            "let xs=[\n1,\n2];\n",
+        );
+
+        assert_eq!(format_source(source).unwrap(), concat!(
+            "fn add(x: number, y) -> number {\n",
+            "    return x + y;\n",
+            "}\n",
+            "let t = (1,);\n",
+            "let u = [1, 2];\n",
+            "let m = { a: 1, \"b\": -2 };\n",
+            "for (let i = 0; i < 3; i = i + 1) println(i);\n",
+            "if (a > 1) {\n",
+            "    println(\"big\");\n",
+            "} else if (a < 0) println(\"neg\");\n",
+            "else {}\n",
+            "echo match (a) {\n",
+            "    1 | 2 => \"small\",\n",
+            "    3..10 => \"mid\",\n",
+            "    n if n > 100 => \"big\",\n",
+            "};\n",
+            "let g = fn (a, [b, ...rest]) {\n",
+            "    return await add(a, b: b);\n",
+            "};\n",
+            "let xs = [\n",
+            "    1,\n",
+            "    2,\n",
+            "];\n",
+        ));
+    }
+
+    #[test]
+    fn comments_and_empty_lines_are_kept() {
+        let source = concat!(
+            "#!/usr/bin/env zawa\n",
+            "// first\n",
+            "\n\n\n",
+            "class A {   // methods\n",
+            "  fn f(self) { return 1; }   \n",
+            "\n",
+            "  // g is next\n",
+            "  fn g(self) {}\n",
+            "  // nothing after g\n",
+            "}\n",
+            "let xs = [\n",
+            "  1, // one\n",
+            "  2 // two\n",
+            "];\n",
+            "// last\n",
+        );
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(formatted, concat!(
+            "#!/usr/bin/env zawa\n",
+            "// first\n",
+            "\n",
+            "class A { // methods\n",
+            "    fn f(self) {\n",
+            "        return 1;\n",
+            "    }\n",
+            "\n",
+            "    // g is next\n",
+            "    fn g(self) {}\n",
+            "    // nothing after g\n",
+            "}\n",
+            "let xs = [\n",
+            "    1, // one\n",
+            "    2, // two\n",
+            "];\n",
+            "// last\n",
+        ));
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn invalid_source_is_not_formatted() {
+        assert!(format_source("let = 1;").is_err());
+        assert!(format_source("println(\"a);").is_err());
+        assert_eq!(format_source("").unwrap(), "");
+    }
+
+    // Tokens of `source` without commas, which the formatter adds and drops at the end of lists
+    fn lexemes(source: &str) -> (Vec<String>, Vec<String>) {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = tokenizer.tokenize().unwrap();
+
+        (
+            tokens.into_iter().filter(|token| token.token_type != TokenType::Comma).map(|token| token.lexeme).collect(),
+            tokenizer.comments().into_iter().map(|comment| comment.text).collect(),
+        )
+    }
+
+    #[test]
+    fn cases_keep_their_tokens_and_comments() {
+        let cases = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/cases")).unwrap();
+
+        for path in cases.map(|entry| entry.unwrap().path()) {
+            if path.extension().is_none_or(|extension| extension != "zw") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            // Cases of syntax errors
+            let Ok(formatted) = format_source(&source) else {
+                continue;
+            };
+
+            assert_eq!(lexemes(&formatted), lexemes(&source), "{}", path.display());
+            assert_eq!(format_source(&formatted).unwrap(), formatted, "{}", path.display());
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use crate::class::InstanceValue;
+use crate::environment::Environment;
+use crate::expr::{Expr, LiteralValue};
+use crate::interpreter::{self, Interpreter};
+use crate::pattern::Pattern;
+use crate::sandbox;
+use crate::stmt::Stmt;
+use crate::tokenizer::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Suspended body of a `fn*` or `async fn` call. Statements that can reach a `yield`
+// or an `await` are stepped through with an explicit stack of frames so the body can
+// stop in the middle and continue on the next resume, every other statement runs on
+// the interpreter.
+pub struct GeneratorState {
+    pub name: String,
+    frames: Vec<Frame>,
+    // Statement stopped at an `await`, it is finished with the value the task is resumed with
+    pending: Option<(Stmt, Rc<RefCell<Environment>>)>,
+    pub done: bool,
+}
+
+pub enum Resumed {
+    // A `yield` value, or the value of an `await` for tasks
+    Suspended(LiteralValue),
+    Finished(LiteralValue),
+}
+
+struct Frame {
+    stmts: Vec<Stmt>,
+    index: usize,
+    environment: Rc<RefCell<Environment>>,
+    kind: FrameKind,
+}
+
+enum FrameKind {
+    Block,
+    While { condition: Expr },
+    ForIn { pattern: Pattern, values: ValueIterator, outer: Rc<RefCell<Environment>>, line_number: usize },
+    // Body of a `try`, errors inside it continue with the handler
+    Try { name: Token, handler: Box<Stmt> },
+}
+
+impl FrameKind {
+    fn is_loop(&self) -> bool {
+        matches!(self, FrameKind::While { .. } | FrameKind::ForIn { .. })
+    }
+}
+
+impl GeneratorState {
+    pub fn new(name: String, body: Vec<Stmt>, environment: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            name,
+            frames: vec![Frame { stmts: body, index: 0, environment, kind: FrameKind::Block }],
+            pending: None,
+            done: false,
+        }
+    }
+
+    // Runs the body until the next `yield` or `await`, `sent` is the result of the
+    // `await` the body stopped at, an error is raised where the body stopped
+    pub fn resume(&mut self, sent: Result<LiteralValue, String>) -> Result<Resumed, String> {
+        let mut result = match self.pending.take() {
+            Some((stmt, environment)) => sent.and_then(|value| self.run(&with_awaited(stmt, value), environment)),
+            None => Ok(None),
+        };
+
+        loop {
+            match result {
+                Ok(Some(resumed)) => return Ok(resumed),
+                Ok(None) => (),
+                Err(msg) => self.catch(msg)?,
+            }
+            result = self.step();
+        }
+    }
+
+    // Runs the next statement, Some once the body suspended or finished
+    fn step(&mut self) -> Result<Option<Resumed>, String> {
+        sandbox::step()?;
+
+        let Some(frame) = self.frames.last_mut() else {
+            self.done = true;
+            return Ok(Some(Resumed::Finished(LiteralValue::Null)));
+        };
+
+        if frame.index >= frame.stmts.len() {
+            self.end_of_frame()?;
+            return Ok(None);
+        }
+
+        let stmt = frame.stmts[frame.index].clone();
+        let environment = frame.environment.clone();
+        frame.index += 1;
+
+        if let Some(awaited) = stmt.awaited() {
+            let value = awaited.evaluate(environment.clone())?;
+            self.pending = Some((stmt, environment));
+            return Ok(Some(Resumed::Suspended(value)));
+        }
+
+        match stmt {
+            Stmt::Yield { keyword: _, value } => {
+                let value = match value {
+                    Some(value) => value.evaluate(environment)?,
+                    None => LiteralValue::Null,
+                };
+                return Ok(Some(Resumed::Suspended(value)));
+            },
+            Stmt::Block { statements } if statements.iter().any(|stmt| stmt.can_suspend()) => {
+                let mut block_environment = Environment::new();
+                block_environment.enclosing = Some(environment);
+
+                self.frames.push(Frame {
+                    stmts: statements.into_iter().map(|stmt| *stmt).collect(),
+                    index: 0,
+                    environment: Rc::new(RefCell::new(block_environment)),
+                    kind: FrameKind::Block,
+                });
+            },
+            Stmt::If { ref predicate, ref then, ref otherwise } if stmt.can_suspend() => {
+                let branch = if predicate.evaluate(environment.clone())?.is_truthy() == LiteralValue::True {
+                    Some(then.as_ref().clone())
+                } else {
+                    otherwise.as_ref().map(|otherwise| otherwise.as_ref().clone())
+                };
+
+                if let Some(branch) = branch {
+                    self.frames.push(Frame { stmts: vec![branch], index: 0, environment, kind: FrameKind::Block });
+                }
+            },
+            Stmt::While { ref condition, ref body } if stmt.can_suspend() => {
+                if condition.evaluate(environment.clone())?.is_truthy() == LiteralValue::True {
+                    self.frames.push(Frame {
+                        stmts: vec![body.as_ref().clone()],
+                        index: 0,
+                        environment,
+                        kind: FrameKind::While { condition: condition.clone() },
+                    });
+                }
+            },
+            Stmt::ForIn { ref keyword, ref pattern, ref iterable, ref body } if stmt.can_suspend() => {
+                let iterable = iterable.evaluate(environment.clone())?;
+                let values = ValueIterator::new(&iterable, keyword.line_number)?;
+
+                // Starts at the end so the first value is bound like every later one
+                self.frames.push(Frame {
+                    stmts: vec![body.as_ref().clone()],
+                    index: 1,
+                    environment: environment.clone(),
+                    kind: FrameKind::ForIn {
+                        pattern: pattern.clone(),
+                        values,
+                        outer: environment,
+                        line_number: keyword.line_number,
+                    },
+                });
+            },
+            Stmt::Try { ref body, ref name, ref handler } if stmt.can_suspend() => {
+                self.frames.push(Frame {
+                    stmts: vec![body.as_ref().clone()],
+                    index: 0,
+                    environment,
+                    kind: FrameKind::Try { name: name.clone(), handler: handler.clone() },
+                });
+            },
+            Stmt::Break => self.break_loop(),
+            stmt => return self.run(&stmt, environment),
+        }
+
+        Ok(None)
+    }
+
+    // Continues with the handler of the innermost `try`, the body fails when there is none
+    fn catch(&mut self, msg: String) -> Result<(), String> {
+        while !sandbox::exceeded()
+            && let Some(frame) = self.frames.pop() {
+            if let FrameKind::Try { name, handler } = frame.kind {
+                let mut handler_environment = Environment::new();
+                handler_environment.enclosing = Some(frame.environment);
+                handler_environment.define(name.lexeme, interpreter::error_value(msg));
+
+                self.frames.push(Frame {
+                    stmts: vec![*handler],
+                    index: 0,
+                    environment: Rc::new(RefCell::new(handler_environment)),
+                    kind: FrameKind::Block,
+                });
+                return Ok(());
+            }
+        }
+
+        self.finish();
+        Err(msg)
+    }
+
+    // Runs a statement that can not suspend, Some once it returned from the body
+    fn run(&mut self, stmt: &Stmt, environment: Rc<RefCell<Environment>>) -> Result<Option<Resumed>, String> {
+        let mut interpreter = Interpreter::with_environment(environment);
+        interpreter.interpret(vec![stmt])?;
+
+        if let Some(value) = interpreter.specials.borrow().get("return") {
+            self.finish();
+            return Ok(Some(Resumed::Finished(value)));
+        }
+        if interpreter.is_breaking() {
+            self.break_loop();
+        }
+
+        Ok(None)
+    }
+
+    fn end_of_frame(&mut self) -> Result<(), String> {
+        let frame = self.frames.last_mut().expect("Generator has no frame");
+
+        match &mut frame.kind {
+            FrameKind::Block | FrameKind::Try { .. } => {
+                self.frames.pop();
+            },
+            FrameKind::While { condition } => {
+                if condition.evaluate(frame.environment.clone())?.is_truthy() == LiteralValue::True {
+                    frame.index = 0;
+                } else {
+                    self.frames.pop();
+                }
+            },
+            FrameKind::ForIn { pattern, values, outer, line_number } => match values.next()? {
+                Some(value) => {
+                    let mut loop_environment = Environment::new();
+                    loop_environment.enclosing = Some(outer.clone());
+                    let loop_environment = Rc::new(RefCell::new(loop_environment));
+
+                    for (name, value) in pattern.destructure(&value, loop_environment.clone(), *line_number)? {
+                        loop_environment.borrow_mut().define(name, value);
+                    }
+
+                    frame.environment = loop_environment;
+                    frame.index = 0;
+                },
+                None => {
+                    self.frames.pop();
+                },
+            },
+        }
+
+        Ok(())
+    }
+
+    // Drops every frame up to and including the innermost loop
+    fn break_loop(&mut self) {
+        while let Some(frame) = self.frames.pop() {
+            if frame.kind.is_loop() {
+                break;
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.frames.clear();
+        self.done = true;
+    }
+}
+
+// Replaces the `await` of a statement returned by `Stmt::awaited` with its result
+fn with_awaited(stmt: Stmt, value: LiteralValue) -> Stmt {
+    let value = Expr::Literal { value };
+
+    match stmt {
+        Stmt::Expression { expression: Expr::Assign { name, value: _ } } => {
+            Stmt::Expression { expression: Expr::Assign { name, value: Box::new(value) } }
+        },
+        Stmt::Let { keyword, pattern, type_annotation, initializer: _ } => {
+            Stmt::Let { keyword, pattern, type_annotation, initializer: value }
+        },
+        Stmt::Return { keyword, value: _ } => Stmt::Return { keyword, value: Some(value) },
+        _ => Stmt::Expression { expression: value },
+    }
+}
+
+// Steps through the values a `for-in` loop walks over
+pub enum ValueIterator {
+    // Lists are read live so items pushed during the loop are visited too
+    List { items: Rc<RefCell<Vec<LiteralValue>>>, index: usize },
+    Values { values: std::vec::IntoIter<LiteralValue> },
+    Generator { state: Rc<RefCell<GeneratorState>> },
+    // Instances with a `next()` method, iteration stops when it returns null
+    Instance { instance: Rc<InstanceValue>, line_number: usize },
+}
+
+impl ValueIterator {
+    pub fn new(value: &LiteralValue, line_number: usize) -> Result<Self, String> {
+        match value {
+            LiteralValue::List(items) => Ok(ValueIterator::List { items: items.clone(), index: 0 }),
+            LiteralValue::Tuple(items) => Ok(ValueIterator::Values { values: Vec::clone(items).into_iter() }),
+            LiteralValue::StringValue(s) => Ok(ValueIterator::Values {
+                values: s.chars().map(|c| LiteralValue::StringValue(c.to_string())).collect::<Vec<_>>().into_iter()
+            }),
+            // Maps give (key, value) tuples
+            LiteralValue::Map(map) => Ok(ValueIterator::Values {
+                values: map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| LiteralValue::Tuple(Rc::new(vec![key.clone(), value.clone()])))
+                    .collect::<Vec<_>>()
+                    .into_iter()
+            }),
+            LiteralValue::Generator(state) => Ok(ValueIterator::Generator { state: state.clone() }),
+            LiteralValue::Instance(instance) if instance.class.methods.contains_key("next") => {
+                Ok(ValueIterator::Instance { instance: instance.clone(), line_number })
+            },
+            other => Err(format!("line: {}, {} is not iterable", line_number, other.to_type())),
+        }
+    }
+
+    pub fn next(&mut self) -> Result<Option<LiteralValue>, String> {
+        match self {
+            ValueIterator::List { items, index } => {
+                let item = items.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(item)
+            },
+            ValueIterator::Values { values } => Ok(values.next()),
+            ValueIterator::Generator { state } => resume(state),
+            ValueIterator::Instance { instance, line_number } => {
+                match InstanceValue::call_method(instance, "next", Vec::new(), *line_number) {
+                    Some(Ok(LiteralValue::Null)) | None => Ok(None),
+                    Some(result) => result.map(Some),
+                }
+            },
+        }
+    }
+}
+
+pub fn resume(state: &Rc<RefCell<GeneratorState>>) -> Result<Option<LiteralValue>, String> {
+    match state.try_borrow_mut() {
+        Ok(state) if state.done => Ok(None),
+        Ok(mut state) => match state.resume(Ok(LiteralValue::Null))? {
+            Resumed::Suspended(value) => Ok(Some(value)),
+            Resumed::Finished(_) => Ok(None),
+        },
+        Err(_) => Err(String::from("Generator is already running")),
+    }
+}
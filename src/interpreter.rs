@@ -1,11 +1,18 @@
 use crate::tokenizer::Token;
-use crate::expr::LiteralValue;
-use crate::stmt::Stmt;
+use crate::call_stack;
+use crate::class::{ClassValue, InstanceValue, TraitValue};
+use crate::event_loop::Task;
+use crate::generator::{GeneratorState, ValueIterator};
+use crate::expr::{EnumType, Expr, LiteralValue};
+use crate::ordered_map::OrderedMap;
+use crate::pattern::Pattern;
+use crate::sandbox::{self, Capabilities};
+use crate::streams::Streams;
+use crate::stmt::{FunctionKind, Stmt};
 use crate::environment::Environment;
-use std::io::ErrorKind::UnexpectedEof;
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::io;
+use std::collections::HashMap;
 use std::io::Read;
 
 fn safe_f64_to_i32(value: f64) -> Result<i32, String> {
@@ -24,85 +31,89 @@ pub struct Interpreter {
     pub specials: Rc<RefCell<Environment>>,
     pub environment: Rc<RefCell<Environment>>,
     should_break: bool,
+    // Whether `return f(...)` hands the call to the caller instead of making it,
+    // only function bodies outside of `try` can do that
+    tail_calls: bool,
+    // Output and input of the script, shared by every interpreter running it
+    pub streams: Rc<Streams>,
 }
 
-fn time_impl(_args: &Vec<LiteralValue>) -> LiteralValue {
+fn time_impl(_args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
-        .expect("Could not get system time")
+        .map_err(|_| String::from("Could not get system time"))?
         .as_millis();
 
-    LiteralValue::Number(now as f64 / 1000.0)
+    Ok(LiteralValue::Number(now as f64 / 1000.0))
 }
 
-fn print_impl(args: &Vec<LiteralValue>) -> LiteralValue {
-    print!("{}", args[0].to_string());
+fn print_impl(streams: &Streams, args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    streams.write(&args[0].try_to_string()?)?;
 
-    LiteralValue::Null
+    Ok(LiteralValue::Null)
 }
 
-fn println_impl(args: &Vec<LiteralValue>) -> LiteralValue {
-    println!("{}", args[0].to_string());
+fn println_impl(streams: &Streams, args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    streams.write(&format!("{}\n", args[0].try_to_string()?))?;
 
-    LiteralValue::Null
+    Ok(LiteralValue::Null)
 }
 
-fn read_impl(_args: &Vec<LiteralValue>) -> LiteralValue {
-    let mut input_text = String::new();
-    let mut buffer = [0; 1];
-
-    loop {
-        match io::stdin().read_exact(&mut buffer) {
-            Ok(_) => {
-                let c = buffer[0] as char;
+fn read_impl(streams: &Streams) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::StringValue(streams.read_word()?))
+}
 
-                if c.is_whitespace() {
-                    if !input_text.is_empty() {
-                        break;
-                    }
-                    continue;
-                }
+fn readln_impl(streams: &Streams) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::StringValue(streams.read_line()?))
+}
 
-                input_text.push(c);
-            }
-            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
-            Err(_) => panic!("Failed to read from stdin")
-        }
+fn read_file_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::StringValue(path) => {
+            // One byte over the size limit is enough to tell the file is too big
+            let limit = sandbox::max_size().map_or(u64::MAX, |max_size| max_size as u64 + 1);
+            let mut bytes = Vec::new();
+
+            std::fs::File::open(path)
+                .and_then(|file| file.take(limit).read_to_end(&mut bytes))
+                .map_err(|msg| format!("Could not read {}: {}", path, msg))?;
+            sandbox::check_size("String", bytes.len())?;
+
+            String::from_utf8(bytes)
+                .map(LiteralValue::StringValue)
+                .map_err(|_| format!("Could not read {}: stream did not contain valid UTF-8", path))
+        },
+        other => Err(format!("Expected a path but got {}", other.to_type()))
     }
-
-    LiteralValue::StringValue(input_text)
 }
 
-fn readln_impl(_args: &Vec<LiteralValue>) -> LiteralValue {
-    let mut input_text = String::new();
-    
-    io::stdin()
-        .read_line(&mut input_text
-        ).expect("Failed to read line from stdin");
-    
-    let clean_line = input_text.trim_end().to_string();
-    
-    LiteralValue::StringValue(clean_line)
+fn write_file_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match (&args[0], &args[1]) {
+        (LiteralValue::StringValue(path), text) => std::fs::write(path, text.to_string())
+            .map(|_| LiteralValue::Null)
+            .map_err(|msg| format!("Could not write {}: {}", path, msg)),
+        (other, _) => Err(format!("Expected a path but got {}", other.to_type()))
+    }
 }
 
-fn number_impl(args: &Vec<LiteralValue>) -> LiteralValue {
+fn number_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
     match &args[0] {
         LiteralValue::Number(x) => {
-            LiteralValue::Number(*x)
+            Ok(LiteralValue::Number(*x))
         },
         LiteralValue::StringValue(s) => {
             match s.parse::<f64>() {
-                Ok(num) => LiteralValue::Number(num),
-                Err(msg) => panic!("{}", msg)
+                Ok(num) => Ok(LiteralValue::Number(num)),
+                Err(msg) => Err(msg.to_string())
             }
         },
-        LiteralValue::True => LiteralValue::Number(1 as f64),
-        LiteralValue::False => LiteralValue::Number(0 as f64),
-        _ => panic!("Expected a number")
+        LiteralValue::True => Ok(LiteralValue::Number(1 as f64)),
+        LiteralValue::False => Ok(LiteralValue::Number(0 as f64)),
+        _ => Err(String::from("Expected a number"))
     }
 }
 
-fn string_impl(args: &Vec<LiteralValue>) -> LiteralValue {
+fn string_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
     // match &args[0] {
     //     LiteralValue::Number(x) => {
     //         LiteralValue::StringValue(x.to_string())
@@ -114,109 +125,308 @@ fn string_impl(args: &Vec<LiteralValue>) -> LiteralValue {
     //     LiteralValue::Null => LiteralValue::StringValue(String::from("null"))
     // }
 
-    LiteralValue::StringValue(LiteralValue::to_string(&args[0]))
+    let text = args[0].try_to_string()?;
+    sandbox::check_size("String", text.len())?;
+
+    Ok(LiteralValue::StringValue(text))
+}
+
+fn boolean_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+   Ok(LiteralValue::is_truthy(&args[0]))
+}
+
+fn type_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+   Ok(LiteralValue::StringValue(String::from(LiteralValue::to_type(&args[0]))))
+}
+
+fn len_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::StringValue(s) => Ok(LiteralValue::Number(s.chars().count() as f64)),
+        LiteralValue::List(items) => Ok(LiteralValue::Number(items.borrow().len() as f64)),
+        LiteralValue::Map(map) => Ok(LiteralValue::Number(map.borrow().len() as f64)),
+        LiteralValue::Tuple(items) => Ok(LiteralValue::Number(items.len() as f64)),
+        other => Err(format!("{} has no length", other.to_type()))
+    }
+}
+
+fn push_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::List(items) => {
+            let mut items = items.borrow_mut();
+            sandbox::check_size("List", items.len() + 1)?;
+            items.push(args[1].clone());
+        },
+        other => return Err(format!("Can not push to {}", other.to_type()))
+    }
+
+    Ok(LiteralValue::Null)
+}
+
+fn tag_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::Variant(variant) => Ok(LiteralValue::StringValue(variant.tag.clone())),
+        other => Err(format!("{} is not an enum variant", other.to_type()))
+    }
+}
+
+fn payload_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::Variant(variant) => Ok(LiteralValue::Tuple(Rc::new(variant.values.clone()))),
+        other => Err(format!("{} is not an enum variant", other.to_type()))
+    }
+}
+
+fn implements_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    let trait_ = match &args[1] {
+        LiteralValue::Trait(trait_) => trait_,
+        other => return Err(format!("Expected a trait but got {}", other.to_type()))
+    };
+
+    match &args[0] {
+        LiteralValue::Instance(instance) => Ok(LiteralValue::from_bool(instance.class.implements(trait_))),
+        LiteralValue::Class(class) => Ok(LiteralValue::from_bool(class.implements(trait_))),
+        _ => Ok(LiteralValue::False)
+    }
+}
+
+fn sleep_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match args[0] {
+        LiteralValue::Number(ms) if ms >= 0.0 => Ok(LiteralValue::Task(Task::sleep(ms)?)),
+        ref other => Err(format!("Expected a number of milliseconds but got {}", other.to_repr()))
+    }
+}
+
+fn spawn_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::Task(task) => Ok(LiteralValue::Task(task.clone())),
+        LiteralValue::Callable { arity: 0, .. } => Ok(LiteralValue::Task(Task::spawn(args[0].clone()))),
+        other => Err(format!("Expected a task or a callable without parameters but got {}", other.to_type()))
+    }
 }
 
-fn boolean_impl(args: &Vec<LiteralValue>) -> LiteralValue {
-   LiteralValue::is_truthy(&args[0])
+fn gather_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::List(items) => Ok(LiteralValue::Task(Task::gather(items.borrow().clone()))),
+        LiteralValue::Tuple(items) => Ok(LiteralValue::Task(Task::gather(items.to_vec()))),
+        other => Err(format!("Expected a list of tasks but got {}", other.to_type()))
+    }
 }
 
-fn type_impl(args: &Vec<LiteralValue>) -> LiteralValue {
-   LiteralValue::StringValue(String::from(LiteralValue::to_type(&args[0])))
+// Value a `catch` binds, an Error instance holding the message of the runtime error
+// and the calls it was raised in as its stack, innermost call last
+pub fn error_value(message: String) -> LiteralValue {
+    let class = ClassValue { name: String::from("Error"), methods: HashMap::new(), traits: Vec::new() };
+    let error = InstanceValue { class: Rc::new(class), fields: RefCell::new(OrderedMap::new()) };
+    error.set("message", LiteralValue::StringValue(message));
+
+    let stack = call_stack::take_trace()
+        .iter()
+        .map(|frame| LiteralValue::StringValue(frame.to_string()))
+        .collect();
+    error.set("stack", LiteralValue::List(Rc::new(RefCell::new(stack))));
+
+    LiteralValue::Instance(Rc::new(error))
 }
 
-fn exit_impl(args: &Vec<LiteralValue>) -> LiteralValue {
+fn exit_impl(args: &Vec<LiteralValue>) -> Result<LiteralValue, String> {
     match args[0] {
         LiteralValue::Number(x) => {
             match safe_f64_to_i32(x) {
                 Ok(code) => std::process::exit(code),
-                Err(msg) => panic!("{}", msg)
+                Err(msg) => Err(msg)
             }
         },
-        _ => panic!("Expected a number")
+        _ => Err(String::from("Expected a number"))
     }
-
-    LiteralValue::Null
 }
 
 impl Interpreter {
-    pub fn new() -> Self {
+    // Natives outside of `capabilities` are left out
+    pub fn with_capabilities(capabilities: Capabilities) -> Self {
         let mut env = Environment::new();
+        let streams = Rc::new(Streams::stdio());
+        env.streams = Some(streams.clone());
+
+        if capabilities.time {
+            env.define_const(
+                String::from("time"), LiteralValue::Callable {
+                name: "time".to_string(),
+                arity: 0,
+                params: Vec::new(),
+                fn_: Rc::new(time_impl)
+            });
+        }
 
-        env.define(
-            String::from("time"), LiteralValue::Callable {
-            name: "time".to_string(),
-            arity: 0,
-            fn_: Rc::new(time_impl)
-        });
-
-        env.define(
+        let print_streams = streams.clone();
+        env.define_const(
             String::from("print"), LiteralValue::Callable {
             name: "print".to_string(),
             arity: 1,
-            fn_: Rc::new(print_impl)
+            params: vec![String::from("arg")],
+            fn_: Rc::new(move |args| print_impl(&print_streams, args))
         });
 
-        env.define(
+        let println_streams = streams.clone();
+        env.define_const(
             String::from("println"), LiteralValue::Callable {
             name: "println".to_string(),
             arity: 1,
-            fn_: Rc::new(println_impl)
+            params: vec![String::from("arg")],
+            fn_: Rc::new(move |args| println_impl(&println_streams, args))
         });
 
-        env.define(
-            String::from("exit"), LiteralValue::Callable {
-            name: "exit".to_string(),
-            arity: 1,
-            fn_: Rc::new(exit_impl)
-        });
+        if capabilities.exit {
+            env.define_const(
+                String::from("exit"), LiteralValue::Callable {
+                name: "exit".to_string(),
+                arity: 1,
+                params: vec![String::from("arg")],
+                fn_: Rc::new(exit_impl)
+            });
+        }
 
-        env.define(
-            String::from("read"), LiteralValue::Callable {
-            name: "read".to_string(),
-            arity: 0,
-            fn_: Rc::new(read_impl)
-        });
+        if capabilities.stdin {
+            let read_streams = streams.clone();
+            env.define_const(
+                String::from("read"), LiteralValue::Callable {
+                name: "read".to_string(),
+                arity: 0,
+                params: Vec::new(),
+                fn_: Rc::new(move |_args| read_impl(&read_streams))
+            });
+        }
 
-        env.define(
-            String::from("readln"), LiteralValue::Callable {
-            name: "readln".to_string(),
-            arity: 0,
-            fn_: Rc::new(readln_impl)
-        });
+        if capabilities.stdin {
+            let readln_streams = streams.clone();
+            env.define_const(
+                String::from("readln"), LiteralValue::Callable {
+                name: "readln".to_string(),
+                arity: 0,
+                params: Vec::new(),
+                fn_: Rc::new(move |_args| readln_impl(&readln_streams))
+            });
+        }
 
-        env.define(
+        env.define_const(
             String::from("number"), LiteralValue::Callable {
             name: "number".to_string(),
             arity: 1,
+            params: vec![String::from("arg")],
             fn_: Rc::new(number_impl)
         });
 
-        env.define(
+        env.define_const(
             String::from("string"), LiteralValue::Callable {
             name: "string".to_string(),
             arity: 1,
+            params: vec![String::from("arg")],
             fn_: Rc::new(string_impl)
         });
 
-        env.define(
+        env.define_const(
             String::from("boolean"), LiteralValue::Callable {
             name: "boolean".to_string(),
             arity: 1,
+            params: vec![String::from("arg")],
             fn_: Rc::new(boolean_impl)
         });
 
-        env.define(
+        env.define_const(
             String::from("type"), LiteralValue::Callable {
             name: "type".to_string(),
             arity: 1,
+            params: vec![String::from("arg")],
             fn_: Rc::new(type_impl)
         });
 
+        env.define_const(
+            String::from("len"), LiteralValue::Callable {
+            name: "len".to_string(),
+            arity: 1,
+            params: vec![String::from("arg")],
+            fn_: Rc::new(len_impl)
+        });
+
+        env.define_const(
+            String::from("push"), LiteralValue::Callable {
+            name: "push".to_string(),
+            arity: 2,
+            params: vec![String::from("list"), String::from("value")],
+            fn_: Rc::new(push_impl)
+        });
+
+        env.define_const(
+            String::from("tag"), LiteralValue::Callable {
+            name: "tag".to_string(),
+            arity: 1,
+            params: vec![String::from("arg")],
+            fn_: Rc::new(tag_impl)
+        });
+
+        env.define_const(
+            String::from("payload"), LiteralValue::Callable {
+            name: "payload".to_string(),
+            arity: 1,
+            params: vec![String::from("arg")],
+            fn_: Rc::new(payload_impl)
+        });
+
+        env.define_const(
+            String::from("implements"), LiteralValue::Callable {
+            name: "implements".to_string(),
+            arity: 2,
+            params: vec![String::from("value"), String::from("trait")],
+            fn_: Rc::new(implements_impl)
+        });
+
+        env.define_const(
+            String::from("sleep"), LiteralValue::Callable {
+            name: "sleep".to_string(),
+            arity: 1,
+            params: vec![String::from("ms")],
+            fn_: Rc::new(sleep_impl)
+        });
+
+        env.define_const(
+            String::from("spawn"), LiteralValue::Callable {
+            name: "spawn".to_string(),
+            arity: 1,
+            params: vec![String::from("fn")],
+            fn_: Rc::new(spawn_impl)
+        });
+
+        env.define_const(
+            String::from("gather"), LiteralValue::Callable {
+            name: "gather".to_string(),
+            arity: 1,
+            params: vec![String::from("tasks")],
+            fn_: Rc::new(gather_impl)
+        });
+
+        if capabilities.file_io {
+            env.define_const(
+                String::from("read_file"), LiteralValue::Callable {
+                name: "read_file".to_string(),
+                arity: 1,
+                params: vec![String::from("path")],
+                fn_: Rc::new(read_file_impl)
+            });
+
+            env.define_const(
+                String::from("write_file"), LiteralValue::Callable {
+                name: "write_file".to_string(),
+                arity: 2,
+                params: vec![String::from("path"), String::from("text")],
+                fn_: Rc::new(write_file_impl)
+            });
+        }
+
         Self {
             specials: Rc::new(RefCell::new(Environment::new())),
             environment: Rc::new(RefCell::new(env)),
-            should_break: false
+            should_break: false,
+            tail_calls: false,
+            streams
         }
     }
 
@@ -224,25 +434,196 @@ impl Interpreter {
         let environment = Rc::new(RefCell::new(Environment::new()));
         environment.borrow_mut().enclosing = Some(parent_env);
 
+        let streams = environment.borrow().streams();
+
         Self {
             specials: Rc::new(RefCell::new(Environment::new())),
             environment: environment,
-            should_break: false
+            should_break: false,
+            tail_calls: true,
+            streams
+        }
+    }
+
+    // Runs statements directly in `environment`, used to resume generator bodies
+    pub fn with_environment(environment: Rc<RefCell<Environment>>) -> Self {
+        let streams = environment.borrow().streams();
+
+        Self {
+            specials: Rc::new(RefCell::new(Environment::new())),
+            environment,
+            should_break: false,
+            tail_calls: false,
+            streams
         }
     }
 
+    pub fn is_returning(&self) -> bool {
+        self.specials.borrow().get("return").is_some()
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.should_break
+    }
+
     pub fn anon_function(parent: Rc<RefCell<Environment>>) -> Self {
+        let streams = parent.borrow().streams();
         let mut env = Environment::new();
         env.enclosing = Some(parent);
         Self {
             specials: Rc::new(RefCell::new(Environment::new())),
             environment: Rc::new(RefCell::new(env)),
-            should_break: false
+            should_break: false,
+            tail_calls: true,
+            streams
+        }
+    }
+
+    // Callable for a declared function, its body runs in a scope enclosed by `parent_env`
+    fn function_value(name: &Token, params: &[Pattern], body: &[Box<Stmt>], parent_env: Rc<RefCell<Environment>>) -> LiteralValue {
+        let arity = params.len();
+        let param_names: Vec<String> = params.iter().map(|p| p.param_name()).collect();
+
+        let params: Vec<Pattern> = params.iter().map(|p| (*p).clone()).collect();
+        let body: Vec<Box<Stmt>> = body.iter().map(|b| (*b).clone()).collect();
+        
+        let name_line = name.line_number;
+
+        let function_impl = move |args: &Vec<LiteralValue>| {
+            let mut clos_int = Interpreter::for_closure(parent_env.clone());
+            
+            for (i, arg) in args.iter().enumerate() {
+                let bindings = params[i].destructure(arg, clos_int.environment.clone(), name_line)?;
+                for (name, value) in bindings {
+                    clos_int.environment.borrow_mut().define(name, value);
+                }
+            }
+
+            for stmt in body.iter() {
+                clos_int.interpret(vec![stmt.as_ref()])?;
+                if let Some(value) = clos_int.specials.borrow().get("return") {
+                    return Ok(value);
+                }
+            }
+
+            Ok(LiteralValue::Null)
+        };
+
+        LiteralValue::Callable {
+            name: name.lexeme.clone(),
+            arity: arity,
+            params: param_names,
+            fn_: Rc::new(function_impl)
+        }
+    }
+
+    fn callable_value(kind: FunctionKind, name: &Token, params: &[Pattern], body: &[Box<Stmt>], parent_env: Rc<RefCell<Environment>>) -> LiteralValue {
+        match kind {
+            FunctionKind::Normal => Interpreter::function_value(name, params, body, parent_env),
+            kind => Interpreter::coroutine_value(kind, name, params, body, parent_env),
+        }
+    }
+
+    // Callable for a `fn*` or `async fn` declaration, calling it binds the arguments and
+    // returns a generator or a scheduled task that has not run any of the body yet
+    fn coroutine_value(
+        kind: FunctionKind,
+        name: &Token,
+        params: &[Pattern],
+        body: &[Box<Stmt>],
+        parent_env: Rc<RefCell<Environment>>
+    ) -> LiteralValue {
+        let param_names: Vec<String> = params.iter().map(|p| p.param_name()).collect();
+        let params = params.to_vec();
+        let body: Vec<Stmt> = body.iter().map(|stmt| stmt.as_ref().clone()).collect();
+
+        let name_clone = name.lexeme.clone();
+        let name_line = name.line_number;
+
+        let generator_impl = move |args: &Vec<LiteralValue>| {
+            let environment = Interpreter::for_closure(parent_env.clone()).environment;
+
+            for (param, arg) in params.iter().zip(args) {
+                let bindings = param.destructure(arg, environment.clone(), name_line)?;
+                for (name, value) in bindings {
+                    environment.borrow_mut().define(name, value);
+                }
+            }
+
+            let state = GeneratorState::new(name_clone.clone(), body.clone(), environment);
+            match kind {
+                FunctionKind::Async => Ok(LiteralValue::Task(Task::coroutine(state))),
+                _ => Ok(LiteralValue::Generator(Rc::new(RefCell::new(state)))),
+            }
+        };
+
+        LiteralValue::Callable {
+            name: name.lexeme.clone(),
+            arity: param_names.len(),
+            params: param_names,
+            fn_: Rc::new(generator_impl)
+        }
+    }
+
+    fn class_value(&self, name: &Token, traits: &[Token], methods: &[Stmt]) -> Result<ClassValue, String> {
+        let mut class_methods = HashMap::new();
+        for method in methods {
+            if let Stmt::Function { name: method, params, param_types: _, return_type: _, body, kind } = method {
+                let method_value = Interpreter::callable_value(*kind, method, params, body, self.environment.clone());
+                class_methods.insert(method.lexeme.clone(), method_value);
+            }
+        }
+
+        let mut class_traits = Vec::new();
+        for trait_name in traits {
+            let trait_ = match self.environment.borrow().get(&trait_name.lexeme) {
+                Some(LiteralValue::Trait(trait_)) => trait_,
+                Some(other) => return Err(format!("line: {}, {} is a {} and not a trait", trait_name.line_number, trait_name.lexeme, other.to_type())),
+                None => return Err(format!("line: {}, Trait {} is not declared", trait_name.line_number, trait_name.lexeme)),
+            };
+
+            let mut missing = Vec::new();
+            for (method, arity) in &trait_.methods {
+                match class_methods.get(method) {
+                    Some(LiteralValue::Callable { arity: class_arity, .. }) if class_arity != arity => return Err(format!(
+                        "line: {}, Method {}.{} takes {} parameters but trait {} declares {}",
+                        name.line_number, name.lexeme, method, class_arity, trait_.name, arity
+                    )),
+                    Some(_) => (),
+                    None => match trait_.defaults.get(method) {
+                        Some(default) => {
+                            class_methods.insert(method.clone(), default.clone());
+                        },
+                        None => missing.push(method.clone()),
+                    },
+                }
+            }
+
+            if !missing.is_empty() {
+                return Err(format!(
+                    "line: {}, Class {} does not implement {} required by trait {}",
+                    name.line_number, name.lexeme, missing.join(", "), trait_.name
+                ));
+            }
+
+            class_traits.push(trait_);
+        }
+
+        Ok(ClassValue { name: name.lexeme.clone(), methods: class_methods, traits: class_traits })
+    }
+
+    fn check_redeclaration(&self, name: &Token) -> Result<(), String> {
+        if self.environment.borrow().is_local_const(&name.lexeme) {
+            return Err(format!("line: {}, {} is a constant and can not be redeclared", name.line_number, name.lexeme));
         }
+
+        Ok(())
     }
 
     pub fn interpret(&mut self, stmts: Vec<&Stmt>) -> Result<(), String> {
         for stmt in stmts {
+            sandbox::step()?;
+
             match stmt {
                 Stmt::Expression { expression } => {
                     expression.evaluate(self.environment.clone())?;
@@ -250,12 +631,30 @@ impl Interpreter {
                 Stmt::Echo { expression } => {
                     let value = expression.evaluate(self.environment.clone())?;
 
-                    println!("{}", value.to_string());
+                    self.streams.write(&format!("{}\n", value.try_to_string()?))?;
+                },
+                Stmt::Let { keyword, pattern, type_annotation: _, initializer } => {
+                    for name in pattern.bindings() {
+                        self.check_redeclaration(name)?;
+                    }
+                    let value = initializer.evaluate(self.environment.clone())?;
+
+                    let bindings = pattern.destructure(&value, self.environment.clone(), keyword.line_number)?;
+                    for (name, value) in bindings {
+                        self.environment.borrow_mut().define(name, value);
+                    }
                 },
-                Stmt::Let { name, initializer } => {
+                Stmt::Const { name, initializer } => {
+                    self.check_redeclaration(name)?;
                     let value = initializer.evaluate(self.environment.clone())?;
 
-                    self.environment.borrow_mut().define(name.lexeme.clone(), value)
+                    self.environment.borrow_mut().define_const(name.lexeme.clone(), value)
+                },
+                Stmt::Enum { name, variants } => {
+                    self.check_redeclaration(name)?;
+                    let enum_type = EnumType::from_declaration(name, variants);
+
+                    self.environment.borrow_mut().define_const(name.lexeme.clone(), LiteralValue::Enum(Rc::new(enum_type)))
                 },
                 Stmt::Block { statements } => {
                     let mut new_environment = Environment::new();
@@ -295,56 +694,132 @@ impl Interpreter {
                             self.should_break = false;
                             break;
                         }
+                        if self.is_returning() {
+                            break;
+                        }
                         flag = condition.evaluate(self.environment.clone())?;
                     }
                 },
+                Stmt::ForIn { keyword, pattern, iterable, body } => {
+                    let iterable = iterable.evaluate(self.environment.clone())?;
+                    let mut values = ValueIterator::new(&iterable, keyword.line_number)?;
+
+                    while let Some(value) = values.next()? {
+                        let mut loop_environment = Environment::new();
+                        loop_environment.enclosing = Some(self.environment.clone());
+                        let loop_environment = Rc::new(RefCell::new(loop_environment));
+
+                        for (name, value) in pattern.destructure(&value, loop_environment.clone(), keyword.line_number)? {
+                            loop_environment.borrow_mut().define(name, value);
+                        }
+
+                        let old_environment = self.environment.clone();
+                        self.environment = loop_environment;
+                        let body_result = self.interpret(vec![body.as_ref()]);
+                        self.environment = old_environment;
+
+                        body_result?;
+                        if self.should_break {
+                            self.should_break = false;
+                            break;
+                        }
+                        if self.is_returning() {
+                            break;
+                        }
+                    }
+                },
+                Stmt::Try { body, name, handler } => {
+                    // A tail call would leave the `try` before the call has run
+                    let tail_calls = std::mem::replace(&mut self.tail_calls, false);
+                    let body_result = self.interpret(vec![body.as_ref()]);
+                    self.tail_calls = tail_calls;
+
+                    if let Err(msg) = body_result {
+                        // Going over a limit ends the script, catching it would let it run on
+                        if sandbox::exceeded() {
+                            return Err(msg);
+                        }
+
+                        let mut handler_environment = Environment::new();
+                        handler_environment.enclosing = Some(self.environment.clone());
+                        handler_environment.define(name.lexeme.clone(), error_value(msg));
+
+                        let old_environment = self.environment.clone();
+                        self.environment = Rc::new(RefCell::new(handler_environment));
+                        let handler_result = self.interpret(vec![handler.as_ref()]);
+                        self.environment = old_environment;
+
+                        handler_result?;
+                    }
+                },
+                Stmt::Yield { keyword, value: _ } => {
+                    return Err(format!("line: {}, yield can only be used inside a generator function", keyword.line_number));
+                },
+                Stmt::Assert { keyword, condition, message } => {
+                    if condition.evaluate(self.environment.clone())?.is_truthy() != LiteralValue::True {
+                        return Err(match message {
+                            Some(message) => format!(
+                                "line: {}, Assertion failed: {}",
+                                keyword.line_number,
+                                message.evaluate(self.environment.clone())?.to_string()
+                            ),
+                            None => format!("line: {}, Assertion failed", keyword.line_number),
+                        });
+                    }
+                },
+                // Run one at a time by `Engine::run_test`
+                Stmt::Test { .. } => (),
                 Stmt::Break => {
                     self.should_break = true;
                     return Ok(());
                 },
-                Stmt::Function { name, params, body } => {
-                    let arity = params.len();
-
-                    let params: Vec<Token> = params.iter().map(|t| (*t).clone()).collect();
-                    let body: Vec<Box<Stmt>> = body.iter().map(|b| (*b).clone()).collect();
-                    
-                    let name_clone = name.lexeme.clone();
-
-                    let parent_env = self.environment.clone();
-                    let function_impl = move |args: &Vec<LiteralValue>| {
-                        let mut clos_int = Interpreter::for_closure(parent_env.clone());
-                        
-                        for (i, arg) in args.iter().enumerate() {
-                            clos_int
-                                .environment
-                                .borrow_mut()
-                                .define(params[i].lexeme.clone(), (*arg).clone());
-                        }
+                Stmt::Function { name, params, param_types: _, return_type: _, body, kind } => {
+                    self.check_redeclaration(name)?;
+                    let callable = Interpreter::callable_value(*kind, name, params, body, self.environment.clone());
 
-                        for i in 0..(body.len()) {
-                            clos_int
-                                .interpret(vec![body[i].as_ref()])
-                                .expect(&format!("Evaluating failed inside {}", name_clone));
-                            if let Some(value) = clos_int.specials.borrow().get("return") {
-                                return value;
-                            }
-                        }
+                    self.environment.borrow_mut().define(name.lexeme.clone(), callable);
+                },
+                Stmt::Class { name, traits, methods } => {
+                    self.check_redeclaration(name)?;
+                    let class = self.class_value(name, traits, methods)?;
 
-                        LiteralValue::Null
-                    };
-                     
-                    let callable = LiteralValue::Callable {
-                        name: name.lexeme.clone(),
-                        arity: arity,
-                        fn_: Rc::new(function_impl)
-                    };
+                    self.environment.borrow_mut().define(name.lexeme.clone(), LiteralValue::Class(Rc::new(class)));
+                },
+                Stmt::Trait { name, signatures, defaults } => {
+                    self.check_redeclaration(name)?;
+
+                    let mut methods: Vec<(String, usize)> = signatures
+                        .iter()
+                        .map(|(method, params)| (method.lexeme.clone(), params.len()))
+                        .collect();
+                    let mut default_methods = HashMap::new();
+                    for default in defaults {
+                        if let Stmt::Function { name: method, params, param_types: _, return_type: _, body, kind } = default {
+                            methods.push((method.lexeme.clone(), params.len()));
+                            default_methods.insert(
+                                method.lexeme.clone(),
+                                Interpreter::callable_value(*kind, method, params, body, self.environment.clone())
+                            );
+                        }
+                    }
 
-                    self.environment.borrow_mut().define(name.lexeme.clone(), callable);
+                    let trait_ = TraitValue { name: name.lexeme.clone(), methods, defaults: default_methods };
+                    self.environment.borrow_mut().define(name.lexeme.clone(), LiteralValue::Trait(Rc::new(trait_)));
                 },
                 Stmt::Return { keyword: _, value } => {
                     let eval_val;
 
-                    if let Some(value) = value {
+                    if let Some(Expr::Call { callee, paren: _, arguments, keyword_arguments }) = value
+                        && self.tail_calls {
+                        let callable = match callee.evaluate(self.environment.clone())? {
+                            LiteralValue::Class(class) => ClassValue::constructor(&class),
+                            other => other,
+                        };
+                        let args = Expr::call_arguments(&callable, arguments, keyword_arguments, self.environment.clone())?;
+
+                        call_stack::tail_call(callable, args);
+                        eval_val = LiteralValue::Null;
+                    } else if let Some(value) = value {
                         eval_val = value.evaluate(self.environment.clone())?;
                     } else {
                         eval_val = LiteralValue::Null;
@@ -355,8 +830,8 @@ impl Interpreter {
                         .define_top_level(String::from("return"), eval_val)
                 }
             };
-            
-            if self.should_break {
+
+            if self.should_break || self.is_returning() {
                 return Ok(());
             }
         }
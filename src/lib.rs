@@ -0,0 +1,55 @@
+// Zawa as a library, hosts run scripts through an Engine and exchange values
+// with them through the conversion traits
+
+mod tokenizer;
+
+mod expr;
+
+mod parser;
+
+mod interpreter;
+
+mod resolver;
+
+mod checker;
+
+mod stmt;
+
+mod pattern;
+
+mod ordered_map;
+
+mod class;
+
+mod generator;
+
+mod event_loop;
+
+mod call_stack;
+
+mod environment;
+
+mod convert;
+
+mod engine;
+
+mod native_object;
+
+mod sandbox;
+
+mod streams;
+
+mod dump;
+
+mod formatter;
+
+pub use crate::call_stack::DEFAULT_MAX_DEPTH;
+pub use crate::convert::{FromValue, IntoArgs, IntoResult, IntoValue, RegisterFn};
+pub use crate::dump::{dump_ast, dump_tokens};
+pub use crate::engine::Engine;
+pub use crate::formatter::format_source;
+pub use crate::expr::LiteralValue;
+pub use crate::native_object::NativeObject;
+pub use crate::sandbox::{Capabilities, Limits};
+pub use crate::streams::OutputBuffer;
+pub use crate::tokenizer::keywords;
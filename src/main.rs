@@ -1,97 +1,262 @@
 const ZAWA_VERSION: &str = "0.15.0";
 
-mod tests;
-
-mod tokenizer;
-use crate::tokenizer::*;
+mod cli;
 
-mod expr;
+mod completion;
 
-mod parser;
-use crate::parser::*;
+mod repl;
 
-mod interpreter;
-use crate::interpreter::*;
-
-mod stmt;
+mod tests;
 
-mod environment;
+use cli::{Command, Source};
+use zawa::Engine;
 
 use std::env;
 use std::process::exit;
 use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Exit statuses of failed commands, from sysexits.h
+const EX_USAGE: i32 = 64;
+// Lex, parse and type errors
+const EX_DATAERR: i32 = 65;
+// Script that does not exist or can not be opened
+const EX_NOINPUT: i32 = 66;
+// Uncaught runtime errors
+const EX_SOFTWARE: i32 = 70;
+const EX_OSERR: i32 = 71;
+const EX_IOERR: i32 = 74;
+
+// Error message of a command and the status the process exits with
+pub struct Failure {
+    status: i32,
+    msg: String,
+}
+
+impl Failure {
+    fn new(status: i32) -> impl Fn(String) -> Failure {
+        move |msg| Failure { status, msg }
+    }
+
+    fn io(what: &str, err: io::Error) -> Failure {
+        let status = match err.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied | io::ErrorKind::IsADirectory => EX_NOINPUT,
+            _ => EX_IOERR,
+        };
+
+        Failure { status, msg: format!("Could not read {}: {}", what, err) }
+    }
+}
+
+fn read_source(source: &Source) -> Result<String, Failure> {
+    match source {
+        Source::File { path } => fs::read_to_string(path).map_err(|err| Failure::io(path, err)),
+        Source::Code { code } => Ok(code.clone()),
+        Source::Stdin => io::read_to_string(io::stdin()).map_err(|err| Failure::io("stdin", err)),
+    }
+}
 
-fn run(interpreter: &mut Interpreter, contents: &str) -> Result<(), String> {
-    let mut tokenizer = Tokenizer::new(contents);
-    let tokens = tokenizer.tokenize()?;
+pub fn run_source(source: &Source, args: Vec<String>) -> Result<(), Failure> {
+    let contents = read_source(source)?;
+    let mut engine = Engine::new();
+    engine.set_args(args);
+
+    engine.validate(&contents).map_err(Failure::new(EX_DATAERR))?;
+    engine.eval(&contents).map(|_| ()).map_err(Failure::new(EX_SOFTWARE))
+}
 
-    let mut parser = Parser::new(tokens);
-    let stmts = parser.parse()?;
-    
-    interpreter.interpret(stmts.iter().collect())?;
+fn print_dump(source: &Source, dump: fn(&str) -> Result<String, String>) -> Result<(), Failure> {
+    let contents = read_source(source)?;
+    print!("{}", dump(&contents).map_err(Failure::new(EX_DATAERR))?);
 
-    return Ok(());
+    Ok(())
 }
 
-pub fn run_file(path: &str) -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
+// Parses and type checks a script without running it
+pub fn check_source(source: &Source) -> Result<(), Failure> {
+    let contents = read_source(source)?;
+    Engine::new().check(&contents).map_err(Failure::new(EX_DATAERR))
+}
+
+// Zawa files under `dir`, hidden directories and build output are skipped
+fn zawa_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Failure> {
+    let entries = fs::read_dir(dir).map_err(|err| Failure::io(&dir.display().to_string(), err))?;
+
+    for entry in entries {
+        let path = entry.map_err(|err| Failure::io(&dir.display().to_string(), err))?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
 
-    match fs::read_to_string(path) {
-        Err(msg) => return Err(msg.to_string()),
-        Ok(contents) => return run(&mut interpreter, &contents),
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                zawa_files(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|extension| extension == "zw") {
+            files.push(path);
+        }
     }
+
+    Ok(())
 }
 
-fn run_prompt() -> Result<(), String> {
-    println!("Zawa {} - REPL", ZAWA_VERSION);
+// Formats a file or every zawa file under a directory in place, code and stdin are printed.
+// With `check` nothing is written and the files that would change are listed, Ok(false) when any would.
+pub fn format_files(source: &Source, check: bool) -> Result<bool, Failure> {
+    let mut files = Vec::new();
+    match source {
+        Source::File { path } if Path::new(path).is_dir() => {
+            zawa_files(Path::new(path), &mut files)?;
+            files.sort();
+        },
+        Source::File { path } => files.push(PathBuf::from(path)),
+        _ => {
+            let contents = read_source(source)?;
+            let formatted = zawa::format_source(&contents).map_err(Failure::new(EX_DATAERR))?;
+            if !check {
+                print!("{}", formatted);
+            }
+            return Ok(formatted == contents);
+        },
+    }
 
-    let mut interpreter = Interpreter::new();
+    let mut formatted_all = true;
+    for file in files {
+        let contents = fs::read_to_string(&file).map_err(|err| Failure::io(&file.display().to_string(), err))?;
+        let formatted = zawa::format_source(&contents)
+            .map_err(|msg| Failure { status: EX_DATAERR, msg: format!("{}\n{}", file.display(), msg) })?;
+        if formatted == contents {
+            continue;
+        }
 
-    loop {
-        print!(">>> ");
-        match io::stdout().flush() {
-            Ok(_) => (),
-            Err(_) => return Err("Couldn't flush stdout".to_string()),
+        formatted_all = false;
+        if check {
+            println!("{}", file.display());
+        } else {
+            fs::write(&file, formatted)
+                .map_err(|err| Failure { status: EX_IOERR, msg: format!("Could not write {}: {}", file.display(), err) })?;
         }
+    }
 
-        let mut buffer = String::new();
-        let stdin = io::stdin();
-        let mut handle = stdin.lock();
-        match handle.read_line(&mut buffer) {
-            Ok(n) => {
-                if n <= 2 {
-                    return Ok(());
+    Ok(formatted_all)
+}
+
+// Runs every test block under `dir`, each one in a fresh engine. Ok(false) when any failed.
+pub fn run_tests(dir: &str) -> Result<bool, Failure> {
+    let mut files = Vec::new();
+    zawa_files(Path::new(dir), &mut files)?;
+    files.sort();
+
+    let mut passed = 0;
+    let mut failures = Vec::new();
+
+    for file in files {
+        let contents = fs::read_to_string(&file).map_err(|err| Failure::io(&file.display().to_string(), err))?;
+        let tests = match Engine::new().tests(&contents) {
+            Ok(tests) => tests,
+            Err(msg) => {
+                println!("test {} ... FAILED", file.display());
+                failures.push(format!("{}\n{}", file.display(), msg));
+                continue;
+            }
+        };
+
+        for (name, line) in tests {
+            match Engine::new().run_test(&contents, &name) {
+                Ok(_) => {
+                    println!("test {}: {} ... ok", file.display(), name);
+                    passed += 1;
+                },
+                Err(msg) => {
+                    println!("test {}: {} ... FAILED", file.display(), name);
+                    failures.push(format!("{}: {} declared on line {}\n{}", file.display(), name, line, msg));
                 }
-            },
-            Err(_) => return Err("Couldn't read line".to_string()),
+            }
         }
+    }
 
-        match run(&mut interpreter, &buffer) {
-            Ok(_) => (),
-            Err(msg) => println!("Error: {}", msg),
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for failure in &failures {
+            println!("\n{}", failure);
         }
     }
+
+    let result = if failures.is_empty() { "ok" } else { "FAILED" };
+    println!("\ntest result: {}. {} passed; {} failed", result, passed, failures.len());
+
+    Ok(failures.is_empty())
 }
 
+// Native stack reserved for every allowed nested call, a call through an
+// operator method takes about 95KiB in debug builds and 8KiB in release ones
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) { 128 * 1024 } else { 64 * 1024 };
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() > 2 {
-        println!("Usage: \"zawa [file]\"");
-        exit(64);
-    } else if args.len() == 2 {
-        match run_file(&args[1]) {
-            Ok(_) => exit(0),
-            Err(msg) => {
-                println!("Error: {}", msg);
-                exit(1);
-            }
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match cli::parse(&args) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("Error: {}\n\n{}", msg, cli::USAGE);
+            exit(EX_USAGE);
         }
-    } else {
-        match run_prompt() {
-            Ok(_) => exit(0),
-            Err(_) => exit(1)
+    };
+    let max_depth = options.max_depth.unwrap_or(zawa::DEFAULT_MAX_DEPTH);
+
+    // The interpreter recurses natively for every call, so it runs on a thread
+    // with a stack big enough for `max_depth` calls
+    let stack_size = max_depth.saturating_mul(STACK_PER_CALL).max(64 * 1024 * 1024);
+    let interpreter_thread = std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            Engine::set_max_depth(max_depth);
+            Engine::set_stack_size(stack_size);
+            run_command(options.command);
+        });
+
+    match interpreter_thread.map(|thread| thread.join()) {
+        Ok(Ok(())) => (),
+        Ok(Err(_)) => exit(101),
+        Err(msg) => {
+            eprintln!("Error: Could not start the interpreter: {}", msg);
+            exit(EX_OSERR);
+        }
+    }
+}
+
+// Prints the error of a command to stderr and exits with its status
+fn finish(result: Result<(), Failure>) {
+    match result {
+        Ok(_) => exit(0),
+        Err(failure) => {
+            eprintln!("Error: {}", failure.msg);
+            exit(failure.status);
         }
     }
-}
\ No newline at end of file
+}
+
+fn run_command(command: Command) {
+    match command {
+        Command::Repl => match repl::run_prompt() {
+            Ok(_) => exit(0),
+            Err(_) => exit(EX_IOERR)
+        },
+        Command::Run { source, args } => finish(run_source(&source, args)),
+        Command::Check { source } => finish(check_source(&source)),
+        // Failed tests are reported on stdout, 1 tells they were not all ok
+        Command::Test { dir } => match run_tests(&dir) {
+            Ok(true) => exit(0),
+            Ok(false) => exit(1),
+            Err(failure) => finish(Err(failure)),
+        },
+        Command::Tokens { source } => finish(print_dump(&source, zawa::dump_tokens)),
+        Command::Ast { source } => finish(print_dump(&source, zawa::dump_ast)),
+        // 1 tells --check found files that are not formatted
+        Command::Fmt { source, check } => match format_files(&source, check) {
+            Ok(false) if check => exit(1),
+            Ok(_) => exit(0),
+            Err(failure) => finish(Err(failure)),
+        },
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Version => println!("zawa {}", ZAWA_VERSION),
+    }
+}
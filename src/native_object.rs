@@ -0,0 +1,128 @@
+use crate::expr::LiteralValue;
+use std::any::Any;
+use std::rc::Rc;
+
+// Opaque Rust value handed to scripts by a host, such as a database handle.
+// Fields and methods are looked up by name through the hooks, objects that
+// change take `&self` and keep their state in cells.
+pub trait NativeObject: Any {
+    // Name `type()` gives for the object
+    fn type_name(&self) -> &str;
+
+    // Value of the field `obj.name`, None when the object has no such field
+    fn get(&self, _name: &str) -> Option<LiteralValue> {
+        None
+    }
+
+    // Runs for `obj.name = value`
+    fn set(&self, name: &str, _value: LiteralValue) -> Result<(), String> {
+        Err(format!("Can not set field {} on {}", name, self.type_name()))
+    }
+
+    // Number of arguments the method `name` takes, None when the object has no such method
+    fn method_arity(&self, _name: &str) -> Option<usize> {
+        None
+    }
+
+    // Runs `obj.name(args)`, the arguments are already checked against `method_arity`
+    fn call(&self, name: &str, _args: Vec<LiteralValue>) -> Result<LiteralValue, String> {
+        Err(format!("{} has no method {}", self.type_name(), name))
+    }
+
+    fn to_string(&self) -> String {
+        format!("native {}", self.type_name())
+    }
+}
+
+// Value of `obj.name`, fields go before methods. Methods are bound to the object.
+pub fn get(object: &Rc<dyn NativeObject>, name: &str) -> Option<LiteralValue> {
+    if let Some(value) = object.get(name) {
+        return Some(value);
+    }
+
+    let arity = object.method_arity(name)?;
+    let bound = object.clone();
+    let method = name.to_string();
+    let call = move |args: &Vec<LiteralValue>| bound.call(&method, args.clone());
+
+    Some(LiteralValue::Callable {
+        name: format!("{}.{}", object.type_name(), name),
+        arity,
+        params: (1..=arity).map(|index| format!("arg{}", index)).collect(),
+        fn_: Rc::new(call),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use std::cell::Cell;
+
+    struct Counter {
+        count: Cell<f64>,
+    }
+
+    impl NativeObject for Counter {
+        fn type_name(&self) -> &str {
+            "Counter"
+        }
+
+        fn get(&self, name: &str) -> Option<LiteralValue> {
+            match name {
+                "count" => Some(LiteralValue::Number(self.count.get())),
+                _ => None,
+            }
+        }
+
+        fn set(&self, name: &str, value: LiteralValue) -> Result<(), String> {
+            match (name, value) {
+                ("count", LiteralValue::Number(count)) => {
+                    self.count.set(count);
+                    Ok(())
+                },
+                (name, value) => Err(format!("Can not set {} of a Counter to {}", name, value.to_type())),
+            }
+        }
+
+        fn method_arity(&self, name: &str) -> Option<usize> {
+            match name {
+                "add" => Some(1),
+                _ => None,
+            }
+        }
+
+        fn call(&self, _name: &str, args: Vec<LiteralValue>) -> Result<LiteralValue, String> {
+            match &args[0] {
+                LiteralValue::Number(step) => {
+                    self.count.set(self.count.get() + step);
+                    Ok(LiteralValue::Null)
+                },
+                other => Err(format!("Can not add {} to a Counter", other.to_type())),
+            }
+        }
+    }
+
+    #[test]
+    fn scripts_use_native_objects() {
+        let mut engine = Engine::new();
+        let counter = Rc::new(Counter { count: Cell::new(0.0) });
+        engine.set("counter", counter.clone()).unwrap();
+        engine.set("other", Rc::new(Counter { count: Cell::new(0.0) })).unwrap();
+
+        engine.eval("counter.add(2); counter.count = counter.count * 10;").unwrap();
+        assert_eq!(counter.count.get(), 20.0);
+
+        assert_eq!(engine.eval("type(counter);").unwrap(), LiteralValue::StringValue(String::from("Counter")));
+        assert_eq!(engine.eval("string(counter);").unwrap(), LiteralValue::StringValue(String::from("native Counter")));
+        assert_eq!(engine.eval("counter == counter;").unwrap(), LiteralValue::True);
+        assert_eq!(engine.eval("counter == other;").unwrap(), LiteralValue::False);
+
+        assert!(engine.eval("counter.add(\"a\");").unwrap_err().contains("Can not add String to a Counter"));
+        assert!(engine.eval("counter.count = \"a\";").unwrap_err().starts_with("line: 1, Can not set count"));
+        assert!(engine.eval("counter.reset();").unwrap_err().contains("Counter has no field or method reset"));
+
+        let same: Rc<Counter> = engine.get("counter").unwrap();
+        assert!(Rc::ptr_eq(&same, &counter));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::expr::LiteralValue;
+use std::collections::HashMap;
+
+// Map that remembers insertion order so printing and iteration are stable
+#[derive(Clone, Default)]
+pub struct OrderedMap {
+    indices: HashMap<LiteralValue, usize>,
+    entries: Vec<(LiteralValue, LiteralValue)>,
+}
+
+impl OrderedMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &LiteralValue) -> Option<&LiteralValue> {
+        self.indices.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn insert(&mut self, key: LiteralValue, value: LiteralValue) {
+        match self.indices.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(LiteralValue, LiteralValue)> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut map = OrderedMap::new();
+        map.insert(LiteralValue::StringValue(String::from("b")), LiteralValue::Number(1.0));
+        map.insert(LiteralValue::StringValue(String::from("a")), LiteralValue::Number(2.0));
+        map.insert(LiteralValue::StringValue(String::from("b")), LiteralValue::Number(3.0));
+
+        let keys = map.iter().map(|(k, _)| k.to_string()).collect::<Vec<String>>();
+        assert_eq!(keys, vec!["b", "a"]);
+        assert_eq!(map.get(&LiteralValue::StringValue(String::from("b"))), Some(&LiteralValue::Number(3.0)));
+    }
+}
@@ -9,7 +9,12 @@ program -> {
 
 declaration -> {
     letDecl |
+    constDecl |
     funcDecl |
+    enumDecl |
+    classDecl |
+    traitDecl |
+    testDecl |
     statement
 }
 
@@ -20,7 +25,27 @@ statement -> {
     ifStmt |
     whileStmt |
     forStmt |
-    breakStmt
+    breakStmt |
+    returnStmt |
+    yieldStmt |
+    tryStmt |
+    assertStmt
+}
+
+assertStmt -> {
+    "assert" expression ("," expression)? ";"
+}
+
+returnStmt -> {
+    "return" expression? ";"
+}
+
+yieldStmt -> {
+    "yield" expression? ";"
+}
+
+tryStmt -> {
+    "try" block "catch" "(" IDENTIFIER ")" block
 }
 
 breakStmt -> {
@@ -28,6 +53,7 @@ breakStmt -> {
 }
 
 forStmt -> {
+    "for" "(" bindingPattern "in" expression ")" statement |
     "for" "("
     ( letDecl | exprStmt | ";") 
     expression? ";"
@@ -55,16 +81,65 @@ echoStmt -> {
 }
 
 funcDecl -> {
-    "fn" function
+    "fn" "*"? function |
+    "async" "fn" function
 }
 
 function -> {
-    IDENTIFIER "(" parametrs? ")"
+    IDENTIFIER "(" parametrs? ")" ("->" typeName)?
     block
 }
 
+parametrs -> {
+    bindingPattern typeAnnotation? ("," bindingPattern typeAnnotation?)*
+}
+
 letDecl -> {
-    "let" IDENTIFIER ("=" expression)? ";"
+    "let" (IDENTIFIER typeAnnotation? ("=" expression)? | bindingPattern typeAnnotation? "=" expression) ";"
+}
+
+typeAnnotation -> {
+    ":" typeName
+}
+
+typeName -> {
+    IDENTIFIER | "null" | "fn"
+}
+
+bindingPattern -> {
+    IDENTIFIER | tuplePattern | listPattern | mapPattern
+}
+
+constDecl -> {
+    "const" IDENTIFIER "=" expression ";"
+}
+
+enumDecl -> {
+    "enum" IDENTIFIER "{" (variant ("," variant)* ","?)? "}"
+}
+
+classDecl -> {
+    "class" IDENTIFIER ("impl" IDENTIFIER ("," IDENTIFIER)*)? "{" ("async"? "fn" "*"? method)* "}"
+}
+
+traitDecl -> {
+    "trait" IDENTIFIER "{" ("fn" methodSignature (";" | block))* "}"
+}
+
+method -> {
+    methodSignature block
+}
+
+methodSignature -> {
+    IDENTIFIER "(" ("self" ("," parametrs)? | parametrs)? ")" ("->" typeName)?
+}
+
+testDecl -> {
+    "test" STRING block
+}
+
+variant -> {
+    IDENTIFIER ("(" (IDENTIFIER ("," IDENTIFIER)* ","?)? ")")?
 }
 
 expression -> {
@@ -75,7 +150,7 @@ function_expression -> {
 }
 
 assignment -> {
-    IDENTIFIER "=" assignment | logic_or
+    (IDENTIFIER | call "[" expression "]" | call "." IDENTIFIER) "=" assignment | logic_or
 }
 
 logic_or -> {
@@ -95,7 +170,67 @@ primary -> {
     "true" | "false" | "null" |
     NUMBER | STRING |
     "(" expression ")" |
-    IDENTIFIER
+    IDENTIFIER | "self" |
+    tuple | list | map | match
+}
+
+tuple -> {
+    "(" ")" | "(" expression "," (expression ("," expression)* ","?)? ")"
+}
+
+list -> {
+    "[" (expression ("," expression)* ","?)? "]"
+}
+
+map -> {
+    "{" (mapKey ":" expression ("," mapKey ":" expression)* ","?)? "}"
+}
+
+mapKey -> {
+    IDENTIFIER | expression
+}
+
+match -> {
+    "match" "(" expression ")" "{" (matchArm ("," matchArm)* ","?)? "}"
+}
+
+matchArm -> {
+    pattern ("if" expression)? "=>" expression
+}
+
+pattern -> {
+    singlePattern ("|" singlePattern)*
+}
+
+singlePattern -> {
+    "_" | IDENTIFIER | patternLiteral |
+    patternLiteral (".." | "..=") patternLiteral |
+    "(" pattern ")" | tuplePattern | listPattern | mapPattern | variantPattern
+}
+
+variantPattern -> {
+    (IDENTIFIER ".")? IDENTIFIER "(" (pattern ("," pattern)* ","?)? ")" |
+    IDENTIFIER "." IDENTIFIER
+}
+
+tuplePattern -> {
+    "(" ")" | "(" pattern "," (pattern ("," pattern)* ","?)? ")"
+}
+
+listPattern -> {
+    "[" (elementPattern ("," elementPattern)* ("," "..." IDENTIFIER)? ","? | "..." IDENTIFIER)? "]"
+}
+
+mapPattern -> {
+    "{" (IDENTIFIER (":" pattern)? ("=" expression)? ("," IDENTIFIER (":" pattern)? ("=" expression)?)* ","?)? "}"
+}
+
+elementPattern -> {
+    pattern ("=" expression)?
+}
+
+patternLiteral -> {
+    "true" | "false" | "null" | STRING | "-"? NUMBER
 }
 
 grouping -> {
@@ -103,15 +238,19 @@ grouping -> {
 }
 
 unary -> {
-    ("-" | "!") unary | call
+    ("-" | "!" | "await") unary | call
 }
 
 call -> {
-    primary ( "(" arguments? ")" )*
+    primary ( "(" arguments? ")" | "[" expression "]" | "." (IDENTIFIER | NUMBER) )*
 }
 
 arguments -> {
-    expression ("," expression)*
+    argument ("," argument)*
+}
+
+argument -> {
+    (IDENTIFIER ":")? expression
 }
 
 binary -> {
@@ -124,20 +263,25 @@ operator -> {
 }
 */
 
-use crate::tokenizer::{TokenType, Token};
-use crate::expr::{Expr, LiteralValue};
-use crate::stmt::Stmt;
+use crate::tokenizer::{self, TokenType, Token};
+use crate::expr::{Expr, LiteralValue, MatchArm};
+use crate::pattern::Pattern;
+use crate::stmt::{FunctionKind, Stmt};
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum FunctionType {
-    Function
+    Function,
+    Method
 }
 
+// Name, parameters, parameter annotations and return annotation of a function
+type FunctionSignature = (Token, Vec<Pattern>, Vec<Option<Token>>, Option<Token>);
+
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
@@ -171,27 +315,70 @@ impl Parser {
     fn declaration(&mut self) -> Result<Stmt, String> {
         if self.match_token(TokenType::Let) {
             self.let_declaration()
+        } else if self.match_token(TokenType::Const) {
+            self.const_declaration()
         } else if self.match_token(TokenType::Fn) {
-            self.function(FunctionType::Function)
+            self.function(FunctionType::Function, false)
+        } else if self.match_token(TokenType::Async) {
+            self.consume(TokenType::Fn, "Expected 'fn' after 'async'")?;
+            self.function(FunctionType::Function, true)
+        } else if self.match_token(TokenType::Enum) {
+            self.enum_declaration()
+        } else if self.match_token(TokenType::Class) {
+            self.class_declaration()
+        } else if self.match_token(TokenType::Trait) {
+            self.trait_declaration()
+        } else if self.check_test() {
+            self.advance();
+            self.test_declaration()
         } else {
             self.statement()
         }
     }
 
-    fn function(&mut self, type_: FunctionType) -> Result<Stmt, String> {
+    fn function(&mut self, type_: FunctionType, is_async: bool) -> Result<Stmt, String> {
+        let kind = if self.match_token(TokenType::Star) {
+            if is_async {
+                return Err(format!("line: {}, An async {:?} can not be a generator", self.previous().line_number, type_));
+            }
+            FunctionKind::Generator
+        } else if is_async {
+            FunctionKind::Async
+        } else {
+            FunctionKind::Normal
+        };
+        let (name, parameters, param_types, return_type) = self.function_signature(&type_)?;
+
+        self.consume(TokenType::LeftBrace, &format!("Expected '{{' before {:?} body", type_))?;
+        let body = match self.block_statement()? {
+            Stmt::Block { statements } => statements,
+            _ => panic!("Block statement parsed something that was not a block")
+        };
+
+        Ok(Stmt::Function { name: name, params: parameters, param_types, return_type, body: body, kind })
+    }
+
+    fn function_signature(&mut self, type_: &FunctionType) -> Result<FunctionSignature, String> {
         let name = self.consume(TokenType::Identifier, &format!("Expected {:?} after name", type_))?;
 
         self.consume(TokenType::LeftParen, &format!("Expected '(' after {:?} name", type_))?;
         
         let mut parameters = Vec::new();
+        let mut param_types = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
                 if parameters.len() > 255 {
                     return Err(format!("line: {}, Can not have more than 255 function arguments", self.peek().line_number));
                 }
 
-                let param = self.consume(TokenType::Identifier, "Expected parameter after name")?;
+                // Methods take the instance as an explicit first `self` parameter
+                let param = if *type_ == FunctionType::Method && parameters.is_empty() && self.match_token(TokenType::Self_) {
+                    Pattern::Binding { name: self.previous() }
+                } else {
+                    self.binding_pattern("Expected parameter after name")?
+                };
                 parameters.push(param);
+                param_types.push(self.type_annotation()?);
 
                 if !self.match_token(TokenType::Comma) {
                     break;
@@ -201,27 +388,161 @@ impl Parser {
 
         self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
 
-        self.consume(TokenType::LeftBrace, &format!("Expected '{{' before {:?} body", type_))?;
-        let body = match self.block_statement()? {
-            Stmt::Block { statements } => statements,
-            _ => panic!("Block statement parsed something that was not a block")
+        let return_type = if self.match_token(TokenType::Arrow) {
+            Some(self.type_name()?)
+        } else {
+            None
         };
 
-        Ok(Stmt::Function { name: name, params: parameters, body: body })
+        Ok((name, parameters, param_types, return_type))
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::Identifier, "Expected class name")?;
+
+        let mut traits = Vec::new();
+        if self.match_token(TokenType::Impl) {
+            loop {
+                traits.push(self.consume(TokenType::Identifier, "Expected trait name after 'impl'")?);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before class body")?;
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let is_async = self.match_token(TokenType::Async);
+            self.consume(TokenType::Fn, "Expected method declaration in class body")?;
+            methods.push(self.function(FunctionType::Method, is_async)?);
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after class body")?;
+
+        Ok(Stmt::Class { name, traits, methods })
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::Identifier, "Expected trait name")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before trait body")?;
+
+        let mut signatures = Vec::new();
+        let mut defaults = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            self.consume(TokenType::Fn, "Expected method declaration in trait body")?;
+            let (method, params, param_types, return_type) = self.function_signature(&FunctionType::Method)?;
+
+            if self.match_token(TokenType::Semicolon) {
+                signatures.push((method, params));
+                continue;
+            }
+
+            self.consume(TokenType::LeftBrace, "Expected ';' or a default body after trait method")?;
+            let body = match self.block_statement()? {
+                Stmt::Block { statements } => statements,
+                _ => panic!("Block statement parsed something that was not a block")
+            };
+            defaults.push(Stmt::Function { name: method, params, param_types, return_type, body, kind: FunctionKind::Normal });
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after trait body")?;
+
+        Ok(Stmt::Trait { name, signatures, defaults })
+    }
+
+    // Optional `: type` after a variable or parameter
+    fn type_annotation(&mut self) -> Result<Option<Token>, String> {
+        if self.match_token(TokenType::Colon) {
+            Ok(Some(self.type_name()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn type_name(&mut self) -> Result<Token, String> {
+        if self.match_tokens(&[TokenType::Identifier, TokenType::Null, TokenType::Fn]) {
+            Ok(self.previous())
+        } else {
+            Err(format!("line: {}, Expected type name", self.peek().line_number))
+        }
     }
 
     fn let_declaration(&mut self) -> Result<Stmt, String> {
-        let token = self.consume(TokenType::Identifier, "Expected variable name")?;
+        let keyword = self.previous();
+        let pattern = self.binding_pattern("Expected variable name")?;
+        let type_annotation = self.type_annotation()?;
 
         let initializer;
         if self.match_token(TokenType::Equal) {
             initializer = self.expression()?;
-        } else {
+        } else if let Pattern::Binding { .. } = pattern {
             initializer = Expr::Literal { value: LiteralValue::Null};
+        } else {
+            return Err(format!("line: {}, Destructuring declaration must be initialised", keyword.line_number));
         }
         
         self.consume(TokenType::Semicolon, "Expected ';' after variable declaration")?;
-        Ok(Stmt::Let { name: token, initializer: initializer})
+        Ok(Stmt::Let { keyword, pattern, type_annotation, initializer })
+    }
+
+    fn const_declaration(&mut self) -> Result<Stmt, String> {
+        let token = self.consume(TokenType::Identifier, "Expected constant name")?;
+
+        if !self.match_token(TokenType::Equal) {
+            return Err(format!("line: {}, Constant {} must be initialised", token.line_number, token.lexeme));
+        }
+        let initializer = self.expression()?;
+
+        self.consume(TokenType::Semicolon, "Expected ';' after constant declaration")?;
+        Ok(Stmt::Const { name: token, initializer })
+    }
+
+    fn enum_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::Identifier, "Expected enum name")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' after enum name")?;
+
+        let mut variants: Vec<(Token, Option<Vec<Token>>)> = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let tag = self.consume(TokenType::Identifier, "Expected variant name")?;
+            if variants.iter().any(|(other, _)| other.lexeme == tag.lexeme) {
+                return Err(format!("line: {}, Enum {} already has a variant named {}", tag.line_number, name.lexeme, tag.lexeme));
+            }
+
+            let fields = if self.match_token(TokenType::LeftParen) {
+                let mut fields: Vec<Token> = Vec::new();
+                while !self.check(TokenType::RightParen) {
+                    let field = self.consume(TokenType::Identifier, "Expected field name in variant")?;
+                    if fields.iter().any(|other| other.lexeme == field.lexeme) {
+                        return Err(format!("line: {}, Variant {} already has a field named {}", field.line_number, tag.lexeme, field.lexeme));
+                    }
+                    fields.push(field);
+
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expected ')' after variant fields")?;
+                Some(fields)
+            } else {
+                None
+            };
+            variants.push((tag, fields));
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after enum variants")?;
+
+        Ok(Stmt::Enum { name, variants })
+    }
+
+    fn test_declaration(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let name = self.consume(TokenType::StringLit, "Expected test name after 'test'")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' after test name")?;
+        let body = self.block_statement()?;
+
+        Ok(Stmt::Test { keyword, name: LiteralValue::from_token(name).to_string(), body: Box::new(body) })
     }
 
     fn statement(&mut self) -> Result<Stmt, String> {
@@ -239,11 +560,57 @@ impl Parser {
             self.break_statement()
         } else if self.match_token(TokenType::Return) {
             self.return_statement()
+        } else if self.match_token(TokenType::Yield) {
+            self.yield_statement()
+        } else if self.match_token(TokenType::Try) {
+            self.try_statement()
+        } else if self.match_token(TokenType::Assert) {
+            self.assert_statement()
         } else {
             self.expression_statement()
         }
     }
 
+    fn try_statement(&mut self) -> Result<Stmt, String> {
+        self.consume(TokenType::LeftBrace, "Expected '{' after 'try'")?;
+        let body = self.block_statement()?;
+
+        self.consume(TokenType::Catch, "Expected 'catch' after try block")?;
+        self.consume(TokenType::LeftParen, "Expected '(' after 'catch'")?;
+        let name = self.consume(TokenType::Identifier, "Expected error name in catch")?;
+        self.consume(TokenType::RightParen, "Expected ')' after error name")?;
+
+        self.consume(TokenType::LeftBrace, "Expected '{' after catch")?;
+        let handler = self.block_statement()?;
+
+        Ok(Stmt::Try { body: Box::new(body), name, handler: Box::new(handler) })
+    }
+
+    fn assert_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let condition = self.expression()?;
+        let message = if self.match_token(TokenType::Comma) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after assert")?;
+
+        Ok(Stmt::Assert { keyword, condition, message })
+    }
+
+    fn yield_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after yield value")?;
+
+        Ok(Stmt::Yield { keyword, value })
+    }
+
     fn return_statement(&mut self) -> Result<Stmt, String> {
         let keyword = self.previous();
         let value;
@@ -266,8 +633,21 @@ impl Parser {
     }
 
     fn for_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
         self.consume(TokenType::LeftParen, "Expected '(' after for")?;
-        
+
+        // `for (pattern in iterable)`, otherwise go back and parse the C style loop
+        let start = self.current;
+        if let Ok(pattern) = self.binding_pattern("Expected loop variable")
+            && self.match_token(TokenType::In) {
+            let iterable = self.expression()?;
+            self.consume(TokenType::RightParen, "Expected ')' after for loop iterable")?;
+            let body = self.statement()?;
+
+            return Ok(Stmt::ForIn { keyword, pattern, iterable, body: Box::new(body) });
+        }
+        self.current = start;
+
         let initializer;
         if self.match_token(TokenType::Semicolon) {
             initializer = None;
@@ -398,7 +778,7 @@ impl Parser {
                     return Err(format!("line: {}, Can not have more than 255 function arguments", self.peek().line_number));
                 }
 
-                let param = self.consume(TokenType::Identifier, "Expected parameter after name")?;
+                let param = self.binding_pattern("Expected parameter after name")?;
                 parameters.push(param);
 
                 if !self.match_token(TokenType::Comma) {
@@ -429,6 +809,8 @@ impl Parser {
 
             match expr {
                 Expr::Variable { name } => Ok(Expr::Assign { name: name, value: Box::from(value) }),
+                Expr::Index { object, bracket, index } => Ok(Expr::SetIndex { object, bracket, index, value: Box::from(value) }),
+                Expr::Get { object, name } => Ok(Expr::Set { object, name, value: Box::from(value) }),
                 _ => Err(format!("Invalid assingment target"))
             }
         } else {
@@ -533,6 +915,13 @@ impl Parser {
                 operator: op,
                 right: Box::from(right)
             })
+        } else if self.match_token(TokenType::Await) {
+            let keyword = self.previous();
+            let value = self.unary()?;
+            Ok(Expr::Await {
+                keyword,
+                value: Box::from(value)
+            })
         } else {
             self.call()
         }
@@ -544,6 +933,13 @@ impl Parser {
         loop {
             if self.match_token(TokenType::LeftParen) {
                 expr = self.finish_call(expr)?;
+            } else if self.match_token(TokenType::Dot) {
+                expr = self.field_access(expr)?;
+            } else if self.match_token(TokenType::LeftBracket) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expected ']' after index")?;
+                expr = Expr::Index { object: Box::new(expr), bracket, index: Box::new(index) };
             } else {
                 break;
             }
@@ -552,14 +948,58 @@ impl Parser {
         Ok(expr)
     }
 
+    fn field_access(&mut self, object: Expr) -> Result<Expr, String> {
+        let name = if self.match_token(TokenType::Number) {
+            self.previous()
+        } else {
+            self.consume(TokenType::Identifier, "Expected field name after '.'")?
+        };
+
+        // `pair.0.1` is tokenized as `pair` `.` `0.1`, so split it back into two fields
+        if name.token_type == TokenType::Number && name.lexeme.contains('.') {
+            let mut expr = object;
+            for field in name.lexeme.split('.') {
+                let token = Token {
+                    token_type: TokenType::Number,
+                    lexeme: field.to_string(),
+                    literal: None,
+                    line_number: name.line_number
+                };
+                expr = Expr::Get { object: Box::new(expr), name: token };
+            }
+            return Ok(expr);
+        }
+
+        Ok(Expr::Get { object: Box::new(object), name })
+    }
+
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, String> {
         let mut arguments = Vec::new();
+        let mut keyword_arguments: Vec<(Token, Expr)> = Vec::new();
         
         if !self.check(TokenType::RightParen) {
             loop {
-                let arg = self.expression()?;
-                arguments.push(arg);
-                if arguments.len() >= 255 {
+                if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                    let name = self.advance();
+                    self.advance();
+
+                    if keyword_arguments.iter().any(|(other, _)| other.lexeme == name.lexeme) {
+                        return Err(format!("line: {}, Keyword argument {} was given more than once", name.line_number, name.lexeme));
+                    }
+
+                    let arg = self.expression()?;
+                    keyword_arguments.push((name, arg));
+                } else {
+                    if !keyword_arguments.is_empty() {
+                        let location = self.peek().line_number;
+                        return Err(format!("line: {}, Positional arguments can not follow keyword arguments", location));
+                    }
+
+                    let arg = self.expression()?;
+                    arguments.push(arg);
+                }
+
+                if arguments.len() + keyword_arguments.len() >= 255 {
                     let location = self.peek().line_number;
                     return Err(format!("line: {}, Can not have more than 255 function arguments", location));
                 }
@@ -571,7 +1011,12 @@ impl Parser {
         }
         let paren = self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
 
-        Ok(Expr::Call { callee: Box::new(callee), paren: paren, arguments: arguments })
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+            keyword_arguments
+        })
     }
 
     fn primary(&mut self) -> Result<Expr, String> {
@@ -581,11 +1026,7 @@ impl Parser {
         match token.token_type {
             TokenType::LeftParen => {
                 self.advance();
-                let expr = self.expression()?;
-                self.consume(TokenType::RightParen, "Expected ')'")?;
-                result = Expr::Grouping {
-                    expression: Box::from(expr)
-                };
+                result = self.grouping_or_tuple()?;
             },
             TokenType::True | TokenType::False | TokenType::Null |  TokenType::Number | TokenType::StringLit => {
                 self.advance();
@@ -593,7 +1034,7 @@ impl Parser {
                     value: LiteralValue::from_token(token.clone())
                 };
             },
-            TokenType::Identifier => {
+            TokenType::Identifier | TokenType::Self_ => {
                 self.advance();
                 result = Expr::Variable { name: self.previous() };
             }
@@ -601,6 +1042,18 @@ impl Parser {
                 self.advance();
                 result = self.function_expression()?;
             },
+            TokenType::LeftBracket => {
+                self.advance();
+                result = self.list_expression()?;
+            },
+            TokenType::LeftBrace => {
+                self.advance();
+                result = self.map_expression()?;
+            },
+            TokenType::Match => {
+                self.advance();
+                result = self.match_expression()?;
+            },
             _ => {
                 return Err(String::from("Expected expression"));
             },
@@ -611,21 +1064,304 @@ impl Parser {
         Ok(result)
     }
 
+    fn grouping_or_tuple(&mut self) -> Result<Expr, String> {
+        if self.match_token(TokenType::RightParen) {
+            return Ok(Expr::Tuple { elements: Vec::new() });
+        }
+
+        let expr = self.expression()?;
+        if !self.match_token(TokenType::Comma) {
+            self.consume(TokenType::RightParen, "Expected ')'")?;
+            return Ok(Expr::Grouping {
+                expression: Box::from(expr)
+            });
+        }
+
+        let mut elements = vec![expr];
+        while !self.check(TokenType::RightParen) {
+            elements.push(self.expression()?);
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightParen, "Expected ')' after tuple elements")?;
+
+        Ok(Expr::Tuple { elements })
+    }
+
+    fn list_expression(&mut self) -> Result<Expr, String> {
+        let mut elements = Vec::new();
+
+        while !self.check(TokenType::RightBracket) {
+            elements.push(self.expression()?);
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBracket, "Expected ']' after list elements")?;
+
+        Ok(Expr::List { elements })
+    }
+
+    fn map_expression(&mut self) -> Result<Expr, String> {
+        let brace = self.previous();
+        let mut entries = Vec::new();
+
+        while !self.check(TokenType::RightBrace) {
+            let key = if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon) {
+                let name = self.advance();
+                Expr::Literal { value: LiteralValue::StringValue(name.lexeme) }
+            } else {
+                self.expression()?
+            };
+            self.consume(TokenType::Colon, "Expected ':' after map key")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after map entries")?;
+
+        Ok(Expr::Map { brace, entries })
+    }
+
+    fn match_expression(&mut self) -> Result<Expr, String> {
+        let keyword = self.previous();
+
+        self.consume(TokenType::LeftParen, "Expected '(' after 'match'")?;
+        let subject = self.expression()?;
+        self.consume(TokenType::RightParen, "Expected ')' after match value")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before match arms")?;
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            let pattern = self.pattern()?;
+
+            let guard = if self.match_token(TokenType::If) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+
+            self.consume(TokenType::FatArrow, "Expected '=>' after match pattern")?;
+            let body = self.expression()?;
+            arms.push(MatchArm { pattern, guard, body });
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after match arms")?;
+
+        Ok(Expr::Match { keyword, subject: Box::new(subject), arms })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        let mut alternatives = vec![self.single_pattern()?];
+
+        while self.match_token(TokenType::Pipe) {
+            alternatives.push(self.single_pattern()?);
+        }
+
+        if alternatives.len() == 1 {
+            Ok(alternatives.remove(0))
+        } else {
+            Ok(Pattern::Or { alternatives })
+        }
+    }
+
+    // `Shape.Circle(r)`, `Circle(r)` or `Shape.Empty`, the enum name is optional
+    // when the fields are given
+    fn variant_pattern(&mut self, first: Token) -> Result<Pattern, String> {
+        let (enum_name, tag) = if self.match_token(TokenType::Dot) {
+            (Some(first), self.consume(TokenType::Identifier, "Expected variant name after '.' in pattern")?)
+        } else {
+            (None, first)
+        };
+
+        let fields = if self.match_token(TokenType::LeftParen) {
+            let mut fields = Vec::new();
+            while !self.check(TokenType::RightParen) {
+                fields.push(self.pattern()?);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after variant pattern")?;
+            Some(fields)
+        } else {
+            None
+        };
+
+        Ok(Pattern::Variant { enum_name, tag, fields })
+    }
+
+    fn single_pattern(&mut self) -> Result<Pattern, String> {
+        if self.match_token(TokenType::Identifier) {
+            let name = self.previous();
+            if self.check(TokenType::Dot) || self.check(TokenType::LeftParen) {
+                return self.variant_pattern(name);
+            }
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            return Ok(Pattern::Binding { name });
+        }
+
+        if self.match_token(TokenType::LeftBracket) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            while !self.check(TokenType::RightBracket) {
+                if self.match_token(TokenType::DotDotDot) {
+                    rest = Some(self.consume(TokenType::Identifier, "Expected name after '...' in list pattern")?);
+                    break;
+                }
+
+                let element = self.pattern()?;
+                elements.push(self.pattern_default(element)?);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expected ']' after list pattern")?;
+
+            return Ok(Pattern::List { elements, rest });
+        }
+
+        if self.match_token(TokenType::LeftParen) {
+            if self.match_token(TokenType::RightParen) {
+                return Ok(Pattern::Tuple { elements: Vec::new() });
+            }
+
+            let first = self.pattern()?;
+            if !self.match_token(TokenType::Comma) {
+                self.consume(TokenType::RightParen, "Expected ')' after pattern")?;
+                return Ok(first);
+            }
+
+            let mut elements = vec![first];
+            while !self.check(TokenType::RightParen) {
+                elements.push(self.pattern()?);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after tuple pattern")?;
+
+            return Ok(Pattern::Tuple { elements });
+        }
+
+        if self.match_token(TokenType::LeftBrace) {
+            let mut entries = Vec::new();
+            while !self.check(TokenType::RightBrace) {
+                let key = self.consume(TokenType::Identifier, "Expected field name in map pattern")?;
+                let pattern = if self.match_token(TokenType::Colon) {
+                    self.pattern()?
+                } else {
+                    Pattern::Binding { name: key.clone() }
+                };
+                entries.push((key, self.pattern_default(pattern)?));
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expected '}' after map pattern")?;
+
+            return Ok(Pattern::Map { entries });
+        }
+
+        let start = self.pattern_literal()?;
+        if self.match_tokens(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.previous().token_type == TokenType::DotDotEqual;
+            let end = self.pattern_literal()?;
+
+            return Ok(Pattern::Range { start, end, inclusive });
+        }
+
+        Ok(Pattern::Literal { value: start })
+    }
+
+    fn pattern_default(&mut self, pattern: Pattern) -> Result<Pattern, String> {
+        if self.match_token(TokenType::Equal) {
+            let default = self.expression()?;
+            Ok(Pattern::Default { pattern: Box::new(pattern), default })
+        } else {
+            Ok(pattern)
+        }
+    }
+
+    // Patterns allowed where a value is always bound: `let` targets and function parameters
+    fn binding_pattern(&mut self, msg: &str) -> Result<Pattern, String> {
+        if self.check(TokenType::Identifier) || self.check(TokenType::LeftBracket)
+            || self.check(TokenType::LeftBrace) || self.check(TokenType::LeftParen) {
+            self.single_pattern()
+        } else {
+            Err(self.name_error(msg))
+        }
+    }
+
+    fn pattern_literal(&mut self) -> Result<LiteralValue, String> {
+        let token = self.peek();
+
+        match token.token_type {
+            TokenType::True | TokenType::False | TokenType::Null | TokenType::Number | TokenType::StringLit => {
+                self.advance();
+                Ok(LiteralValue::from_token(token))
+            },
+            TokenType::Minus => {
+                self.advance();
+                let number = self.consume(TokenType::Number, "Expected number after '-' in pattern")?;
+                match LiteralValue::from_token(number) {
+                    LiteralValue::Number(x) => Ok(LiteralValue::Number(-x)),
+                    _ => panic!("Number token did not hold a number"),
+                }
+            },
+            _ => Err(format!("line: {}, Expected pattern", token.line_number)),
+        }
+    }
+
     fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<Token, String> {
         let token = self.peek();
         if token.token_type == token_type {
             self.advance();
             let token = self.previous();
             Ok(token)
+        } else if token_type == TokenType::Identifier {
+            Err(self.name_error(msg))
         } else {
             Err(String::from(msg))
         }
     }
 
+    // Error for a missing name, which names the keyword when one is in its place
+    fn name_error(&mut self, msg: &str) -> String {
+        let token = self.peek();
+        if tokenizer::keywords().contains(&token.lexeme.as_str()) {
+            format!("line: {}, {} is a keyword and can not be used as a name", token.line_number, token.lexeme)
+        } else {
+            String::from(msg)
+        }
+    }
+
     fn check(&mut self, type_: TokenType) -> bool {
         self.peek().token_type == type_
     }
 
+    // `test` is only a keyword in front of a test name, elsewhere it is a name
+    fn check_test(&mut self) -> bool {
+        self.check(TokenType::Identifier) && self.peek().lexeme == "test" && self.check_next(TokenType::StringLit)
+    }
+
+    fn check_next(&mut self, type_: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => token.token_type == type_,
+            None => false,
+        }
+    }
+
     fn match_token(&mut self, type_: TokenType) -> bool {
         if self.is_at_end() {
             false
@@ -676,9 +1412,9 @@ impl Parser {
                 return;
             }
             match self.peek().token_type {
-                TokenType::Class | TokenType::Fn | TokenType::Let |
+                TokenType::Class | TokenType::Fn | TokenType::Async | TokenType::Let | TokenType::Const | TokenType::Enum | TokenType::Trait |
                 TokenType::For | TokenType::If | TokenType::While |
-                TokenType::Echo | TokenType::Return => return,
+                TokenType::Echo | TokenType::Return | TokenType::Yield | TokenType::Try | TokenType::Assert => return,
                 _ => (),
             }
             self.advance();
@@ -706,4 +1442,221 @@ mod tests {
 
         assert_eq!(string_expr, "(== 1 (group (+ 2 3)))")
     }
+
+    #[test]
+    fn test_call_with_keyword_arguments() {
+        let src = "f(1, b: 2);";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+        let string_expr = parsed_expr[0].to_string();
+
+        assert_eq!(string_expr, "(var f [1] b: 2)")
+    }
+
+    #[test]
+    fn test_positional_after_keyword_argument() {
+        let src = "f(a: 1, 2);";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        assert!(parser.parse().is_err());
+    }
+
+    #[test]
+    fn test_enum_declaration_and_variant_pattern() {
+        let src = "enum Shape { Circle(r), Empty } match (s) { Shape.Circle(r) => r, Rect(_, h) => h, Shape.Empty => 0 };";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+
+        assert_eq!(parsed_expr[0].to_string(), "(enum Shape (Circle r) Empty)");
+        assert_eq!(parsed_expr[1].to_string(), "(match var s (Shape.Circle(r) => var r) (Rect(_, h) => var h) (Shape.Empty => 0))")
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        let src = "fn add(a: number, b) -> number { } let name: string = \"a\";";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+
+        match &parsed_expr[0] {
+            Stmt::Function { param_types, return_type, .. } => {
+                assert_eq!(param_types.iter().map(|t| t.as_ref().map(|t| t.lexeme.as_str())).collect::<Vec<_>>(), vec![Some("number"), None]);
+                assert_eq!(return_type.as_ref().map(|t| t.lexeme.as_str()), Some("number"));
+            },
+            _ => panic!("Expected a function declaration"),
+        }
+        assert_eq!(parsed_expr[1].to_string(), "(let name: string)")
+    }
+
+    #[test]
+    fn test_class_and_trait_declaration() {
+        let src = "trait Named { fn name(self); fn greet(self) { } } class User impl Named { fn name(self) { self.seen = true; } }";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+
+        assert_eq!(parsed_expr[0].to_string(), "(trait Named name greet)");
+        assert_eq!(parsed_expr[1].to_string(), "(class User impl Named name)");
+        match &parsed_expr[1] {
+            Stmt::Class { methods, .. } => match &methods[0] {
+                Stmt::Function { params, body, .. } => {
+                    assert_eq!(params[0].to_string(), "self");
+                    assert_eq!(body[0].to_string(), "(set var self seen true)");
+                },
+                _ => panic!("Expected a method"),
+            },
+            _ => panic!("Expected a class declaration"),
+        }
+    }
+
+    #[test]
+    fn test_for_in_and_yield() {
+        let src = "for ((k, v) in items) echo k; for (let i = 0; i < 1; i = i + 1) { } fn* gen() { yield 1; }";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+
+        assert_eq!(parsed_expr[0].to_string(), "(for (k, v) in var items (print var k))");
+        assert!(matches!(parsed_expr[1], Stmt::Block { .. }));
+        match &parsed_expr[2] {
+            Stmt::Function { body, kind, .. } => {
+                assert_eq!(*kind, FunctionKind::Generator);
+                assert_eq!(body[0].to_string(), "(yield 1)");
+            },
+            _ => panic!("Expected a generator declaration"),
+        }
+    }
+
+    #[test]
+    fn test_async_function_and_await() {
+        let src = "async fn f() { let a = await sleep(1); } let b = await f();";
+        let tokens = Tokenizer::new(src).tokenize().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+
+        match &parsed[0] {
+            Stmt::Function { body, kind, .. } => {
+                assert_eq!(*kind, FunctionKind::Async);
+                assert!(body[0].awaited().is_some());
+            },
+            _ => panic!("Expected an async function declaration"),
+        }
+        assert_eq!(parsed[1].to_string(), "(let b)");
+        assert!(parsed[1].awaited().is_some());
+
+        let tokens = Tokenizer::new("async fn* f() {}").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_try_statement() {
+        let tokens = Tokenizer::new("try { f(); } catch (e) { println(e); }").tokenize().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+
+        assert_eq!(parsed[0].to_string(), "(try (block (var f [])) catch e (block (var println [var e])))");
+
+        let tokens = Tokenizer::new("try { f(); }").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_assert_and_test_declaration() {
+        let tokens = Tokenizer::new("test \"adds\" { assert 1 + 1 == 2, \"math\"; assert ok; }").tokenize().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+
+        assert_eq!(parsed[0].to_string(), "(test \"adds\" (block (assert (== (+ 1 1) 2) math)(assert var ok)))");
+
+        let tokens = Tokenizer::new("test adds { }").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let src = "match (x) { 1 | 2 => a, [h, _] if h => b, { name } => name, 0..=9 => c };";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+        let string_expr = parsed_expr[0].to_string();
+
+        assert_eq!(string_expr, "(match var x (1 | 2 => var a) ([h, _] if var h => var b) ({ name } => var name) (0..=9 => var c))")
+    }
+
+    #[test]
+    fn test_tuple_and_nested_field_access() {
+        let src = "(1, (2,), ()).1.0;";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed_expr = parser.parse().unwrap();
+        let string_expr = parsed_expr[0].to_string();
+
+        assert_eq!(string_expr, "(get (get (tuple 1 (tuple 2) (tuple )) 1) 0)")
+    }
+
+    #[test]
+    fn test_test_is_a_name_outside_of_tests() {
+        let src = "let test = 1; test(test); test \"adds\" { }";
+
+        let mut tokenizer = Tokenizer::new(src);
+
+        let tokens = tokenizer.tokenize().unwrap();
+
+        let mut parser = Parser::new(tokens);
+
+        let parsed = parser.parse().unwrap();
+
+        assert!(matches!(parsed[0], Stmt::Let { .. }));
+        assert!(matches!(parsed[1], Stmt::Expression { .. }));
+        assert!(matches!(parsed[2], Stmt::Test { .. }));
+    }
+
+    #[test]
+    fn test_keyword_as_name() {
+        let tokens = Tokenizer::new("let while = 1;").tokenize().unwrap();
+
+        let Err(msg) = Parser::new(tokens).parse() else {
+            panic!("A keyword was parsed as a name");
+        };
+        assert_eq!(msg, "line: 1, while is a keyword and can not be used as a name");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,353 @@
+use crate::environment::Environment;
+use crate::expr::{Expr, LiteralValue};
+use crate::tokenizer::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub enum Pattern {
+    Wildcard,
+    Literal { value: LiteralValue },
+    Binding { name: Token },
+    Range { start: LiteralValue, end: LiteralValue, inclusive: bool },
+    List { elements: Vec<Pattern>, rest: Option<Token> },
+    Tuple { elements: Vec<Pattern> },
+    Map { entries: Vec<(Token, Pattern)> },
+    Or { alternatives: Vec<Pattern> },
+    Default { pattern: Box<Pattern>, default: Expr },
+    // Without fields any payload matches, `Shape.Circle` matches every circle
+    Variant { enum_name: Option<Token>, tag: Token, fields: Option<Vec<Pattern>> },
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Literal { value } => write!(f, "{}", value.to_repr()),
+            Pattern::Binding { name } => write!(f, "{}", name.lexeme),
+            Pattern::Range { start, end, inclusive } => write!(
+                f,
+                "{}{}{}",
+                start.to_repr(),
+                if *inclusive { "..=" } else { ".." },
+                end.to_repr()
+            ),
+            Pattern::List { elements, rest } => {
+                let mut parts = elements.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+                if let Some(rest) = rest {
+                    parts.push(format!("...{}", rest.lexeme));
+                }
+                write!(f, "[{}]", parts.join(", "))
+            },
+            Pattern::Tuple { elements } if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            Pattern::Tuple { elements } => write!(
+                f,
+                "({})",
+                elements.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(", ")
+            ),
+            Pattern::Map { entries } => write!(
+                f,
+                "{{ {} }}",
+                entries.iter().map(|(key, p)| match p {
+                    Pattern::Binding { name } if name.lexeme == key.lexeme => key.lexeme.clone(),
+                    p => format!("{}: {}", key.lexeme, p),
+                }).collect::<Vec<String>>().join(", ")
+            ),
+            Pattern::Or { alternatives } => write!(
+                f,
+                "{}",
+                alternatives.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(" | ")
+            ),
+            Pattern::Default { pattern, default } => write!(f, "{} = {}", pattern, default.to_string()),
+            Pattern::Variant { enum_name, tag, fields } => {
+                if let Some(enum_name) = enum_name {
+                    write!(f, "{}.", enum_name.lexeme)?;
+                }
+                write!(f, "{}", tag.lexeme)?;
+                if let Some(fields) = fields {
+                    write!(f, "({})", fields.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(", "))?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Pattern {
+    // Names this pattern binds when it matches, used by the resolver
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Binding { name } => vec![name],
+            Pattern::List { elements, rest } => {
+                let mut names: Vec<&Token> = elements.iter().flat_map(|p| p.bindings()).collect();
+                if let Some(rest) = rest
+                    && rest.lexeme != "_" {
+                    names.push(rest);
+                }
+                names
+            },
+            Pattern::Tuple { elements } => elements.iter().flat_map(|p| p.bindings()).collect(),
+            Pattern::Map { entries } => entries.iter().flat_map(|(_, p)| p.bindings()).collect(),
+            Pattern::Or { alternatives } => alternatives.iter().flat_map(|p| p.bindings()).collect(),
+            Pattern::Default { pattern, default: _ } => pattern.bindings(),
+            Pattern::Variant { enum_name: _, tag: _, fields } => fields.iter().flatten().flat_map(|p| p.bindings()).collect(),
+            Pattern::Wildcard | Pattern::Literal { .. } | Pattern::Range { .. } => Vec::new(),
+        }
+    }
+
+    // Default value expressions anywhere in the pattern, used by the resolver
+    pub fn defaults(&self) -> Vec<&Expr> {
+        match self {
+            Pattern::List { elements, rest: _ } => elements.iter().flat_map(|p| p.defaults()).collect(),
+            Pattern::Tuple { elements } => elements.iter().flat_map(|p| p.defaults()).collect(),
+            Pattern::Map { entries } => entries.iter().flat_map(|(_, p)| p.defaults()).collect(),
+            Pattern::Or { alternatives } => alternatives.iter().flat_map(|p| p.defaults()).collect(),
+            Pattern::Default { pattern, default } => {
+                let mut defaults = pattern.defaults();
+                defaults.push(default);
+                defaults
+            },
+            Pattern::Variant { enum_name: _, tag: _, fields } => fields.iter().flatten().flat_map(|p| p.defaults()).collect(),
+            Pattern::Wildcard | Pattern::Literal { .. } | Pattern::Binding { .. } | Pattern::Range { .. } => Vec::new(),
+        }
+    }
+
+    // Tests `value` against the pattern, pushing every bound name to `bindings`.
+    // On a failed match `bindings` may contain partial results and should be discarded.
+    // Errors only come from evaluating default values
+    pub fn matches(
+        &self,
+        value: &LiteralValue,
+        environment: Rc<RefCell<Environment>>,
+        bindings: &mut Vec<(String, LiteralValue)>
+    ) -> Result<bool, String> {
+        match self {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Literal { value: expected } => Ok(value == expected),
+            Pattern::Binding { name } => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                Ok(true)
+            },
+            Pattern::Range { start, end, inclusive } => Ok(match (start, end, value) {
+                (LiteralValue::Number(start), LiteralValue::Number(end), LiteralValue::Number(x)) => {
+                    start <= x && (if *inclusive { x <= end } else { x < end })
+                },
+                (LiteralValue::StringValue(start), LiteralValue::StringValue(end), LiteralValue::StringValue(s)) => {
+                    start <= s && (if *inclusive { s <= end } else { s < end })
+                },
+                _ => false,
+            }),
+            Pattern::List { elements, rest } => {
+                let items = match value {
+                    LiteralValue::List(items) => items.borrow().clone(),
+                    _ => return Ok(false),
+                };
+
+                let required = elements.iter().filter(|p| !matches!(p, Pattern::Default { .. })).count();
+                if items.len() < required || (rest.is_none() && items.len() > elements.len()) {
+                    return Ok(false);
+                }
+
+                for (i, element) in elements.iter().enumerate() {
+                    let matched = match (items.get(i), element) {
+                        (Some(item), element) => element.matches(item, environment.clone(), bindings)?,
+                        (None, element) => element.matches_missing(environment.clone(), bindings)?,
+                    };
+                    if !matched {
+                        return Ok(false);
+                    }
+                }
+
+                if let Some(rest) = rest
+                    && rest.lexeme != "_" {
+                    let remaining = items.iter().skip(elements.len()).cloned().collect();
+                    bindings.push((rest.lexeme.clone(), LiteralValue::List(Rc::new(RefCell::new(remaining)))));
+                }
+
+                Ok(true)
+            },
+            Pattern::Tuple { elements } => {
+                let items = match value {
+                    LiteralValue::Tuple(items) if items.len() == elements.len() => items.clone(),
+                    _ => return Ok(false),
+                };
+
+                for (element, item) in elements.iter().zip(items.iter()) {
+                    if !element.matches(item, environment.clone(), bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            },
+            Pattern::Map { entries } => {
+                let map = match value {
+                    LiteralValue::Map(map) => map.clone(),
+                    _ => return Ok(false),
+                };
+
+                for (key, p) in entries {
+                    let field = map.borrow().get(&LiteralValue::StringValue(key.lexeme.clone())).cloned();
+                    let matched = match field {
+                        Some(field) => p.matches(&field, environment.clone(), bindings)?,
+                        None => p.matches_missing(environment.clone(), bindings)?,
+                    };
+                    if !matched {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            },
+            Pattern::Or { alternatives } => {
+                for alternative in alternatives {
+                    let mut alternative_bindings = Vec::new();
+                    if alternative.matches(value, environment.clone(), &mut alternative_bindings)? {
+                        bindings.extend(alternative_bindings);
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            },
+            Pattern::Default { pattern, default: _ } => pattern.matches(value, environment, bindings),
+            Pattern::Variant { enum_name, tag, fields } => {
+                let variant = match value {
+                    LiteralValue::Variant(variant) => variant.clone(),
+                    _ => return Ok(false),
+                };
+
+                if variant.tag != tag.lexeme
+                    || enum_name.as_ref().is_some_and(|name| name.lexeme != variant.enum_name) {
+                    return Ok(false);
+                }
+
+                let fields = match fields {
+                    Some(fields) if fields.len() == variant.values.len() => fields,
+                    Some(_) => return Ok(false),
+                    None => return Ok(true),
+                };
+
+                for (field, item) in fields.iter().zip(variant.values.iter()) {
+                    if !field.matches(item, environment.clone(), bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            },
+        }
+    }
+
+    // Binds an irrefutable position such as a `let` target or a function parameter,
+    // a value of the wrong shape is an error instead of a failed match
+    pub fn destructure(
+        &self,
+        value: &LiteralValue,
+        environment: Rc<RefCell<Environment>>,
+        line_number: usize
+    ) -> Result<Vec<(String, LiteralValue)>, String> {
+        let mut bindings = Vec::new();
+
+        if self.matches(value, environment, &mut bindings)? {
+            Ok(bindings)
+        } else {
+            Err(format!("line: {}, Can not destructure {} with pattern {}", line_number, value.to_repr(), self))
+        }
+    }
+
+    // Name a caller can use for this parameter in keyword arguments
+    pub fn param_name(&self) -> String {
+        match self {
+            Pattern::Binding { name } => name.lexeme.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    // Used for list elements and map keys that are not present in the value
+    fn matches_missing(
+        &self,
+        environment: Rc<RefCell<Environment>>,
+        bindings: &mut Vec<(String, LiteralValue)>
+    ) -> Result<bool, String> {
+        match self {
+            Pattern::Default { pattern, default } => {
+                let value = default.evaluate(environment.clone())?;
+                pattern.matches(&value, environment, bindings)
+            },
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::TokenType;
+
+    fn environment() -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::new()))
+    }
+
+    fn binding(name: &str) -> Pattern {
+        Pattern::Binding {
+            name: Token {
+                token_type: TokenType::Identifier,
+                lexeme: String::from(name),
+                literal: None,
+                line_number: 0
+            }
+        }
+    }
+
+    #[test]
+    fn list_pattern_binds_elements() {
+        let pattern = Pattern::List { elements: vec![binding("a"), Pattern::Wildcard], rest: None };
+        let value = LiteralValue::List(Rc::new(RefCell::new(vec![LiteralValue::Number(1.0), LiteralValue::Null])));
+
+        let mut bindings = Vec::new();
+        assert!(pattern.matches(&value, environment(), &mut bindings).unwrap());
+        assert_eq!(bindings, vec![(String::from("a"), LiteralValue::Number(1.0))]);
+        assert_eq!(pattern.to_string(), "[a, _]");
+    }
+
+    #[test]
+    fn range_pattern() {
+        let pattern = Pattern::Range { start: LiteralValue::Number(1.0), end: LiteralValue::Number(5.0), inclusive: false };
+
+        assert!(pattern.matches(&LiteralValue::Number(1.0), environment(), &mut Vec::new()).unwrap());
+        assert!(!pattern.matches(&LiteralValue::Number(5.0), environment(), &mut Vec::new()).unwrap());
+        assert!(!pattern.matches(&LiteralValue::StringValue(String::from("1")), environment(), &mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn list_pattern_with_rest_and_default() {
+        let pattern = Pattern::List {
+            elements: vec![
+                binding("a"),
+                Pattern::Default {
+                    pattern: Box::new(binding("b")),
+                    default: Expr::Literal { value: LiteralValue::Number(0.0) }
+                }
+            ],
+            rest: Some(Token {
+                token_type: TokenType::Identifier,
+                lexeme: String::from("rest"),
+                literal: None,
+                line_number: 0
+            })
+        };
+        let empty = LiteralValue::List(Rc::new(RefCell::new(Vec::new())));
+
+        let mut bindings = Vec::new();
+        let value = LiteralValue::List(Rc::new(RefCell::new(vec![LiteralValue::Number(1.0)])));
+        assert!(pattern.matches(&value, environment(), &mut bindings).unwrap());
+        assert_eq!(bindings, vec![
+            (String::from("a"), LiteralValue::Number(1.0)),
+            (String::from("b"), LiteralValue::Number(0.0)),
+            (String::from("rest"), empty.clone())
+        ]);
+
+        assert!(!pattern.matches(&empty, environment(), &mut Vec::new()).unwrap());
+        assert_eq!(pattern.to_string(), "[a, b = 0, ...rest]");
+    }
+}
@@ -0,0 +1,257 @@
+// Interactive shell of the zawa binary
+
+use crate::completion::ShellHelper;
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+use zawa::{Engine, LiteralValue};
+
+const PROMPT: &str = ">>> ";
+// Shown while the input so far has brackets that are not closed yet
+const CONTINUATION_PROMPT: &str = "... ";
+
+pub const COMMANDS: [&str; 7] = [":help", ":vars", ":type", ":ast", ":load", ":reset", ":time"];
+
+const HELP: &str = "\
+:help           Print this help
+:vars           List the variables defined in the shell
+:type <code>    Type of the value of an expression
+:ast <code>     Syntax tree of code, without running it
+:load <file>    Run a file in the shell
+:reset          Forget everything defined in the shell
+:time <code>    Run code and print how long it took
+
+The value of an expression is printed, the last ';' can be left out.
+Ctrl-C drops the current input and Ctrl-D exits.";
+
+// Where entries are kept between sessions, None when there is no home directory
+fn history_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| home.join(".zawa_history"))
+}
+
+// Whether `source` opens more brackets than it closes or ends inside a string,
+// brackets in strings and comments do not count
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                if !chars.by_ref().any(|c| c == '"') {
+                    return true;
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            },
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    depth > 0
+}
+
+// Engine of the shell and the globals it started with, :vars leaves those out
+struct Session {
+    engine: Engine,
+    natives: Vec<String>,
+}
+
+impl Session {
+    fn new() -> Self {
+        let engine = Engine::new();
+        let natives = engine.globals().into_iter().map(|(name, _)| name).collect();
+
+        Self { engine, natives }
+    }
+
+    // Adds the `;` that can be left out at the end of the input
+    fn complete(&self, source: &str) -> String {
+        let with_semicolon = format!("{};", source.trim_end());
+        if self.engine.validate(source).is_err() && self.engine.validate(&with_semicolon).is_ok() {
+            with_semicolon
+        } else {
+            source.to_string()
+        }
+    }
+
+    // Runs `source` and gives back what to print, the value of an expression unless it is null
+    fn eval(&mut self, source: &str) -> Result<String, String> {
+        let source = self.complete(source);
+
+        match self.engine.eval(&source)? {
+            LiteralValue::Null => Ok(String::new()),
+            value => Ok(value.to_repr()),
+        }
+    }
+
+    // Runs a line starting with ':' and gives back what to print
+    fn command(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+
+        match (command, argument) {
+            (":help", _) => Ok(String::from(HELP)),
+            (":vars", _) => Ok(self
+                .engine
+                .globals()
+                .into_iter()
+                .filter(|(name, _)| !self.natives.contains(name))
+                .map(|(name, value)| format!("{} = {}", name, value.to_repr()))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            (":reset", _) => {
+                *self = Session::new();
+                Ok(String::new())
+            },
+            (":type" | ":ast" | ":load" | ":time", "") => Err(format!("{} expects an argument, :help lists the commands", command)),
+            (":type", code) => {
+                let source = self.complete(code);
+                Ok(self.engine.eval(&source)?.to_type().to_string())
+            },
+            (":ast", code) => zawa::dump_ast(&self.complete(code)).map(|ast| ast.trim_end().to_string()),
+            (":load", path) => {
+                let contents = fs::read_to_string(path).map_err(|msg| format!("Could not read {}: {}", path, msg))?;
+                self.engine.eval(&contents).map(|_| String::new())
+            },
+            (":time", code) => {
+                let start = Instant::now();
+                let value = self.eval(code)?;
+                let took = format!("took {:.3} ms", start.elapsed().as_secs_f64() * 1000.0);
+
+                Ok(if value.is_empty() { took } else { format!("{}\n{}", value, took) })
+            },
+            _ => Err(format!("Unknown command {}, :help lists the commands", command)),
+        }
+    }
+}
+
+pub fn run_prompt() -> Result<(), String> {
+    println!("Zawa {} - REPL", crate::ZAWA_VERSION);
+
+    let mut editor: Editor<ShellHelper, FileHistory> = Editor::new().map_err(|msg| msg.to_string())?;
+    editor.set_helper(Some(ShellHelper::new()));
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history file before the first session
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = Session::new();
+    let mut buffer = String::new();
+
+    loop {
+        if let Some(helper) = editor.helper_mut() {
+            helper.set_globals(session.engine.globals());
+        }
+
+        let prompt = if buffer.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                buffer.push_str(&line);
+                buffer.push('\n');
+                if !buffer.starts_with(':') && is_incomplete(&buffer) {
+                    continue;
+                }
+            },
+            // Ctrl-C drops the input typed so far
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            },
+            // Ctrl-D
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(msg) => return Err(msg.to_string()),
+        }
+
+        let source = std::mem::take(&mut buffer);
+        if source.trim().is_empty() {
+            continue;
+        }
+
+        // Losing the history is not worth stopping the shell for
+        let _ = editor.add_history_entry(source.trim_end());
+        if let Some(path) = &history {
+            let _ = editor.save_history(path);
+        }
+
+        let result = if source.starts_with(':') {
+            session.command(&source)
+        } else {
+            session.eval(&source)
+        };
+        match result {
+            Ok(output) if output.is_empty() => (),
+            Ok(output) => println!("{}", output),
+            Err(msg) => eprintln!("Error: {}", msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unclosed_brackets_continue_the_input() {
+        assert!(is_incomplete("fn f() {\n"));
+        assert!(is_incomplete("let a = [1,\n2,\n"));
+        assert!(is_incomplete("println(\"a\n"));
+        assert!(is_incomplete("if (a) { println(\")\"); // }\n"));
+
+        assert!(!is_incomplete("fn f() { return 1; }\n"));
+        assert!(!is_incomplete("println(\"{\"); // (\n"));
+        assert!(!is_incomplete("}\n"));
+        assert!(!is_incomplete(""));
+    }
+
+    #[test]
+    fn values_are_printed_and_semicolons_optional() {
+        let mut session = Session::new();
+
+        assert_eq!(session.eval("1 + 2\n").unwrap(), "3");
+        assert_eq!(session.eval("\"a\" + \"b\";\n").unwrap(), "\"ab\"");
+        assert_eq!(session.eval("let a = [1, 2]\n").unwrap(), "");
+        assert_eq!(session.eval("fn f() { return a; }\n").unwrap(), "");
+        assert_eq!(session.eval("f()").unwrap(), "[1, 2]");
+        assert_eq!(session.eval("println(\"\")").unwrap(), "");
+        assert!(session.eval("1 +").is_err());
+    }
+
+    #[test]
+    fn meta_commands() {
+        let mut session = Session::new();
+        session.eval("let b = \"text\"; let a = 1;").unwrap();
+
+        assert_eq!(session.command(":vars").unwrap(), "a = 1\nb = \"text\"");
+        assert_eq!(session.command(":type a + 1").unwrap(), "Number");
+        assert_eq!(session.command(":ast if (a) b = 2").unwrap(), "(if var a b = 2)");
+        assert!(session.command(":time a * 2").unwrap().starts_with("2\ntook "));
+        assert!(session.command(":help").unwrap().contains(":load <file>"));
+
+        session.command(":reset").unwrap();
+        assert_eq!(session.command(":vars").unwrap(), "");
+        assert!(session.eval("a").is_err());
+
+        let path = std::env::temp_dir().join("zawa_repl_load.zw");
+        fs::write(&path, "let loaded = 5;").unwrap();
+        session.command(&format!(":load {}", path.display())).unwrap();
+        assert_eq!(session.eval("loaded").unwrap(), "5");
+
+        assert!(session.command(":type").is_err());
+        assert!(session.command(":wat").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use crate::environment::Environment;
+use crate::expr::Expr;
+use crate::pattern::Pattern;
+use crate::stmt::{FunctionKind, Stmt};
+use crate::tokenizer::Token;
+use std::collections::HashMap;
+
+// Static pass that runs between parsing and interpreting.
+// Every scope maps a declared name to whether it is a constant.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    // Kind of each function being resolved, innermost last
+    functions: Vec<FunctionKind>,
+    // Set while resolving a statement an `await` can suspend a task in
+    awaiting_statement: bool,
+    errors: Vec<String>,
+}
+
+impl Resolver {
+    pub fn new(globals: &Environment) -> Self {
+        let global_scope = globals
+            .constant_names()
+            .into_iter()
+            .map(|name| (name, true))
+            .collect();
+
+        Self {
+            scopes: vec![global_scope],
+            functions: Vec::new(),
+            awaiting_statement: false,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors.join("\n"))
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt) {
+        self.awaiting_statement = stmt.awaited().is_some();
+
+        match stmt {
+            Stmt::Expression { expression } => self.resolve_expr(expression),
+            Stmt::Echo { expression } => self.resolve_expr(expression),
+            Stmt::Let { keyword: _, pattern, type_annotation: _, initializer } => {
+                self.resolve_expr(initializer);
+                self.resolve_pattern(pattern);
+            },
+            Stmt::Const { name, initializer } => {
+                self.resolve_expr(initializer);
+                self.declare(name, true);
+            },
+            Stmt::Block { statements } => {
+                self.scopes.push(HashMap::new());
+                for statement in statements {
+                    self.resolve_stmt(statement);
+                }
+                self.scopes.pop();
+            },
+            Stmt::If { predicate, then, otherwise } => {
+                self.resolve_expr(predicate);
+                self.resolve_stmt(then);
+                if let Some(otherwise) = otherwise {
+                    self.resolve_stmt(otherwise);
+                }
+            },
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(body);
+            },
+            Stmt::Break => (),
+            Stmt::Function { name, params, param_types: _, return_type: _, body, kind } => {
+                self.declare(name, false);
+                self.resolve_function(params, body, *kind);
+            },
+            Stmt::ForIn { keyword: _, pattern, iterable, body } => {
+                self.resolve_expr(iterable);
+                self.scopes.push(HashMap::new());
+                self.resolve_pattern(pattern);
+                self.resolve_stmt(body);
+                self.scopes.pop();
+            },
+            Stmt::Yield { keyword, value } => {
+                if self.functions.last() != Some(&FunctionKind::Generator) {
+                    self.errors.push(format!("line: {}, yield can only be used inside a generator function", keyword.line_number));
+                }
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            },
+            Stmt::Return { keyword: _, value } => {
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            },
+            Stmt::Try { body, name, handler } => {
+                self.resolve_stmt(body);
+                self.scopes.push(HashMap::new());
+                self.declare(name, false);
+                self.resolve_stmt(handler);
+                self.scopes.pop();
+            },
+            Stmt::Enum { name, variants: _ } => self.declare(name, true),
+            Stmt::Class { name, traits: _, methods } => {
+                self.declare(name, false);
+                self.resolve_methods(methods);
+            },
+            Stmt::Trait { name, signatures: _, defaults } => {
+                self.declare(name, false);
+                self.resolve_methods(defaults);
+            },
+            Stmt::Assert { keyword: _, condition, message } => {
+                self.resolve_expr(condition);
+                if let Some(message) = message {
+                    self.resolve_expr(message);
+                }
+            },
+            Stmt::Test { keyword, name: _, body } => {
+                if self.scopes.len() > 1 {
+                    self.errors.push(format!("line: {}, Tests can only be declared at the top level", keyword.line_number));
+                }
+                self.resolve_stmt(body);
+            },
+        }
+    }
+
+    fn resolve_function(&mut self, params: &[Pattern], body: &[Box<Stmt>], kind: FunctionKind) {
+        self.functions.push(kind);
+        self.scopes.push(HashMap::new());
+        for param in params {
+            self.resolve_pattern(param);
+        }
+        for stmt in body {
+            self.resolve_stmt(stmt);
+        }
+        self.scopes.pop();
+        self.functions.pop();
+    }
+
+    fn resolve_methods(&mut self, methods: &[Stmt]) {
+        for method in methods {
+            if let Stmt::Function { name: _, params, param_types: _, return_type: _, body, kind } = method {
+                self.resolve_function(params, body, *kind);
+            }
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::AnonFunction { paren: _, arguments, body } => self.resolve_function(arguments, body, FunctionKind::Normal),
+            Expr::Binary { left, operator: _, right } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            },
+            Expr::Grouping { expression } => self.resolve_expr(expression),
+            Expr::Call { callee, paren: _, arguments, keyword_arguments } => {
+                self.resolve_expr(callee);
+                for argument in arguments {
+                    self.resolve_expr(argument);
+                }
+                for (_, argument) in keyword_arguments {
+                    self.resolve_expr(argument);
+                }
+            },
+            Expr::Literal { value: _ } => (),
+            Expr::Logical { left, operator: _, right } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            },
+            Expr::Unary { operator: _, right } => self.resolve_expr(right),
+            Expr::Variable { name: _ } => (),
+            Expr::Assign { name, value } => {
+                self.resolve_expr(value);
+                if self.lookup(&name.lexeme) == Some(true) {
+                    self.errors.push(format!("line: {}, Can not assign to constant {}", name.line_number, name.lexeme));
+                }
+            },
+            Expr::List { elements } | Expr::Tuple { elements } => {
+                for element in elements {
+                    self.resolve_expr(element);
+                }
+            },
+            Expr::Get { object, name: _ } => self.resolve_expr(object),
+            Expr::Set { object, name: _, value } => {
+                self.resolve_expr(object);
+                self.resolve_expr(value);
+            },
+            Expr::Map { brace: _, entries } => {
+                for (key, value) in entries {
+                    self.resolve_expr(key);
+                    self.resolve_expr(value);
+                }
+            },
+            Expr::Index { object, bracket: _, index } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+            },
+            Expr::SetIndex { object, bracket: _, index, value } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+                self.resolve_expr(value);
+            },
+            Expr::Match { keyword: _, subject, arms } => {
+                self.resolve_expr(subject);
+                for arm in arms {
+                    self.scopes.push(HashMap::new());
+                    self.resolve_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.resolve_expr(guard);
+                    }
+                    self.resolve_expr(&arm.body);
+                    self.scopes.pop();
+                }
+            },
+            Expr::Await { keyword, value } => {
+                // Only the first `await` of a statement is the one the task can suspend in
+                let suspends = std::mem::take(&mut self.awaiting_statement);
+
+                match self.functions.last() {
+                    // Outside of functions the event loop runs until the task has finished
+                    None => (),
+                    Some(FunctionKind::Async) if suspends => (),
+                    Some(FunctionKind::Async) => self.errors.push(format!(
+                        "line: {}, await inside an async function can only be used as a statement, a let initialiser, an assignment or a return value",
+                        keyword.line_number
+                    )),
+                    Some(_) => self.errors.push(format!(
+                        "line: {}, await can only be used inside an async function or outside of functions",
+                        keyword.line_number
+                    )),
+                }
+                self.resolve_expr(value);
+            },
+        }
+    }
+
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        for default in pattern.defaults() {
+            self.resolve_expr(default);
+        }
+        for name in pattern.bindings() {
+            self.declare(name, false);
+        }
+    }
+
+    fn declare(&mut self, name: &Token, constant: bool) {
+        let scope = self.scopes.last_mut().expect("Resolver has no scope");
+
+        if scope.get(&name.lexeme) == Some(&true) {
+            self.errors.push(format!("line: {}, {} is a constant and can not be redeclared", name.line_number, name.lexeme));
+            return;
+        }
+
+        scope.insert(name.lexeme.clone(), constant);
+    }
+
+    // Returns whether the nearest declaration of `name` is a constant,
+    // or None if it is not declared in any scope the resolver has seen
+    fn lookup(&self, name: &str) -> Option<bool> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::tokenizer::Tokenizer;
+
+    fn resolve(src: &str) -> Result<(), String> {
+        let tokens = Tokenizer::new(src).tokenize().unwrap();
+        let stmts = Parser::new(tokens).parse().unwrap();
+
+        let mut globals = Environment::new();
+        globals.define_const(String::from("println"), crate::expr::LiteralValue::Null);
+
+        Resolver::new(&globals).resolve(&stmts)
+    }
+
+    #[test]
+    fn assign_to_const_is_rejected() {
+        assert!(resolve("const a = 1; a = 2;").is_err());
+        assert!(resolve("const a = 1; fn f() { a = 2; }").is_err());
+        assert!(resolve("println = 5;").is_err());
+    }
+
+    #[test]
+    fn shadowed_const_can_be_assigned() {
+        assert!(resolve("const a = 1; { let a = 2; a = 3; }").is_ok());
+        assert!(resolve("const a = 1; fn f(a) { a = 2; }").is_ok());
+    }
+
+    #[test]
+    fn const_can_not_be_redeclared() {
+        assert!(resolve("const a = 1; let a = 2;").is_err());
+        assert!(resolve("let println = 1;").is_err());
+    }
+
+    #[test]
+    fn yield_outside_generator_is_rejected() {
+        assert!(resolve("yield 1;").is_err());
+        assert!(resolve("fn f() { yield 1; }").is_err());
+        assert!(resolve("fn* f() { fn g() { yield 1; } }").is_err());
+        assert!(resolve("fn* f() { while (true) { yield 1; } }").is_ok());
+    }
+
+    #[test]
+    fn await_is_checked_by_position() {
+        assert!(resolve("async fn f() { let a = await g(); a = await g(); await g(); return await g(); }").is_ok());
+        assert!(resolve("let a = 1 + await g();").is_ok());
+        assert!(resolve("async fn f() { let a = 1 + await g(); }").is_err());
+        assert!(resolve("async fn f() { let a = await await g(); }").is_err());
+        assert!(resolve("fn f() { await g(); }").is_err());
+        assert!(resolve("async fn f() { fn h() { await g(); } }").is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::call_stack;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+// Budget for running untrusted scripts, None leaves that resource unlimited
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    // Statements run, every event loop turn counts as one too
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    // Largest string in bytes or list or map in items a script can build
+    pub max_size: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+// Natives installed in a new engine, the rest of the language is always there
+#[derive(Clone, Copy, Debug)]
+pub struct Capabilities {
+    // read and readln
+    pub stdin: bool,
+    pub exit: bool,
+    pub time: bool,
+    // read_file and write_file
+    pub file_io: bool,
+}
+
+impl Capabilities {
+    pub fn all() -> Self {
+        Self { stdin: true, exit: true, time: true, file_io: true }
+    }
+
+    pub fn none() -> Self {
+        Self { stdin: false, exit: false, time: false, file_io: false }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+// What is left of the budget of the script that is running
+struct Budget {
+    max_steps: Option<u64>,
+    steps: u64,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    max_size: Option<usize>,
+    // Error of the limit the script went over, it is raised again on every later
+    // step so a `try` can not swallow it
+    exceeded: Option<String>,
+}
+
+const UNLIMITED: Budget = Budget { max_steps: None, steps: 0, timeout: None, deadline: None, max_size: None, exceeded: None };
+
+thread_local! {
+    static BUDGET: RefCell<Budget> = const { RefCell::new(UNLIMITED) };
+}
+
+// Gives back the budget and call depth that were in place before `start`
+pub struct Running {
+    budget: Budget,
+    max_depth: usize,
+}
+
+// Starts a fresh budget on this thread, it lasts until the guard is dropped
+pub fn start(limits: &Limits) -> Running {
+    let budget = Budget {
+        max_steps: limits.max_steps,
+        steps: 0,
+        timeout: limits.timeout,
+        deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+        max_size: limits.max_size,
+        exceeded: None,
+    };
+
+    let max_depth = call_stack::max_depth();
+    if let Some(depth) = limits.max_depth {
+        call_stack::set_max_depth(depth);
+    }
+
+    Running {
+        budget: BUDGET.with(|current| current.replace(budget)),
+        max_depth,
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let budget = std::mem::replace(&mut self.budget, UNLIMITED);
+        BUDGET.with(|current| current.replace(budget));
+        call_stack::set_max_depth(self.max_depth);
+    }
+}
+
+// Counts one step, the clock is only read every so often to keep steps cheap
+pub fn step() -> Result<(), String> {
+    BUDGET.with(|budget| {
+        let mut budget = budget.borrow_mut();
+        if let Some(msg) = &budget.exceeded {
+            return Err(msg.clone());
+        }
+        budget.steps += 1;
+
+        if let Some(max_steps) = budget.max_steps
+            && budget.steps > max_steps {
+            return exceed(&mut budget, format!("Step limit exceeded, more than {} steps", max_steps));
+        }
+        if budget.steps % 256 == 0
+            && let Err(msg) = check_time(&budget) {
+            return exceed(&mut budget, msg);
+        }
+
+        Ok(())
+    })
+}
+
+fn exceed(budget: &mut Budget, msg: String) -> Result<(), String> {
+    budget.exceeded = Some(msg.clone());
+    Err(msg)
+}
+
+// True once the script went over one of its limits, the error of it is not caught by `try`
+pub fn exceeded() -> bool {
+    BUDGET.with(|budget| budget.borrow().exceeded.is_some())
+}
+
+fn check_time(budget: &Budget) -> Result<(), String> {
+    match (budget.deadline, budget.timeout) {
+        (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+            Err(format!("Time limit exceeded, ran for more than {} ms", timeout.as_millis()))
+        },
+        _ => Ok(()),
+    }
+}
+
+// Raises the time limit error once the deadline has passed, used while waiting
+pub fn check_deadline() -> Result<(), String> {
+    BUDGET.with(|budget| {
+        let mut budget = budget.borrow_mut();
+        match check_time(&budget) {
+            Err(msg) => exceed(&mut budget, msg),
+            Ok(()) => Ok(()),
+        }
+    })
+}
+
+// Time until the deadline, None when there is no time limit
+pub fn time_left() -> Option<Duration> {
+    BUDGET.with(|budget| budget.borrow().deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())))
+}
+
+// Largest string, list or map scripts can build, None without a size limit
+pub fn max_size() -> Option<usize> {
+    BUDGET.with(|budget| budget.borrow().max_size)
+}
+
+// Called when a string, list, map or tuple grows to `size`
+pub fn check_size(type_name: &str, size: usize) -> Result<(), String> {
+    BUDGET.with(|budget| {
+        let mut budget = budget.borrow_mut();
+        match budget.max_size {
+            Some(max_size) if size > max_size => {
+                exceed(&mut budget, format!("Size limit exceeded, {} of size {} is larger than {}", type_name, size, max_size))
+            },
+            _ => Ok(()),
+        }
+    })
+}
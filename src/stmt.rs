@@ -1,21 +1,51 @@
 use crate::expr::Expr;
+use crate::pattern::Pattern;
 use crate::tokenizer::Token;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionKind {
+    Normal,
+    // `fn*`, calling it returns a generator
+    Generator,
+    // `async fn`, calling it schedules a task on the event loop
+    Async,
+}
+
 #[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Stmt {
     Expression { expression: Expr },
     Echo { expression: Expr },
-    Let { name: Token, initializer: Expr },
+    Let { keyword: Token, pattern: Pattern, type_annotation: Option<Token>, initializer: Expr },
+    Const { name: Token, initializer: Expr },
     Block { statements: Vec<Box<Stmt>> },
     If { predicate: Expr, then: Box<Stmt>, otherwise: Option<Box<Stmt>> },
     While { condition: Expr, body: Box<Stmt> },
     Break,
-    Function { name: Token, params: Vec<Token>, body: Vec<Box<Stmt>> },
+    Function {
+        name: Token,
+        params: Vec<Pattern>,
+        param_types: Vec<Option<Token>>,
+        return_type: Option<Token>,
+        body: Vec<Box<Stmt>>,
+        kind: FunctionKind
+    },
     Return { keyword: Token, value: Option<Expr> },
+    ForIn { keyword: Token, pattern: Pattern, iterable: Expr, body: Box<Stmt> },
+    Yield { keyword: Token, value: Option<Expr> },
+    // `name` is bound to an Error instance in the handler
+    Try { body: Box<Stmt>, name: Token, handler: Box<Stmt> },
+    Enum { name: Token, variants: Vec<(Token, Option<Vec<Token>>)> },
+    Class { name: Token, traits: Vec<Token>, methods: Vec<Stmt> },
+    // Signatures are the methods without a default body
+    Trait { name: Token, signatures: Vec<(Token, Vec<Pattern>)>, defaults: Vec<Stmt> },
+    // Fails with `message` when `condition` is not truthy
+    Assert { keyword: Token, condition: Expr, message: Option<Expr> },
+    // Only `zawa test` runs the body, scripts skip it
+    Test { keyword: Token, name: String, body: Box<Stmt> },
 }
 
 impl Stmt {
-    #[allow(dead_code)]
     pub fn to_string(&self) -> String {
         match self {
             Stmt::Expression { expression } => expression.to_string(),
@@ -23,19 +53,144 @@ impl Stmt {
                 "(print {})",
                 expression.to_string()
             ),
-            Stmt::Let { name, initializer: _ } => format!(
+            Stmt::Let { keyword: _, pattern, type_annotation: Some(type_annotation), initializer: _ } => format!(
+                "(let {}: {})",
+                pattern,
+                type_annotation.lexeme
+            ),
+            Stmt::Let { keyword: _, pattern, type_annotation: None, initializer: _ } => format!(
                 "(let {})",
+                pattern
+            ),
+            Stmt::Const { name, initializer: _ } => format!(
+                "(const {})",
                 name.lexeme
             ),
             Stmt::Block { statements } => format!(
                 "(block {})",
                 statements.into_iter().map(|stmt| stmt.to_string()).collect::<String>()
             ),
-            Stmt::If { predicate: _, then: _, otherwise: _ } => todo!(),
-            Stmt::While { condition: _, body: _ } => todo!(),
+            Stmt::If { predicate, then, otherwise: Some(otherwise) } => format!(
+                "(if {} {} else {})",
+                predicate.to_string(),
+                then.to_string(),
+                otherwise.to_string()
+            ),
+            Stmt::If { predicate, then, otherwise: None } => format!(
+                "(if {} {})",
+                predicate.to_string(),
+                then.to_string()
+            ),
+            Stmt::While { condition, body } => format!(
+                "(while {} {})",
+                condition.to_string(),
+                body.to_string()
+            ),
             Stmt::Break => String::from("(break)"),
-            Stmt::Function { name: _, params: _, body: _ } => todo!(),
-            Stmt::Return { keyword: _, value: _ } => todo!(),
+            Stmt::Function { name, params, param_types: _, return_type: _, body, kind } => format!(
+                "({} {} [{}] {})",
+                match kind {
+                    FunctionKind::Normal => "fn",
+                    FunctionKind::Generator => "fn*",
+                    FunctionKind::Async => "async fn",
+                },
+                name.lexeme,
+                params.iter().map(|param| param.to_string()).collect::<Vec<String>>().join(" "),
+                body.iter().map(|stmt| stmt.to_string()).collect::<String>()
+            ),
+            Stmt::Return { keyword: _, value: Some(value) } => format!("(return {})", value.to_string()),
+            Stmt::Return { keyword: _, value: None } => String::from("(return)"),
+            Stmt::ForIn { keyword: _, pattern, iterable, body } => format!(
+                "(for {} in {} {})",
+                pattern,
+                iterable.to_string(),
+                body.to_string()
+            ),
+            Stmt::Yield { keyword: _, value: Some(value) } => format!("(yield {})", value.to_string()),
+            Stmt::Yield { keyword: _, value: None } => String::from("(yield)"),
+            Stmt::Try { body, name, handler } => format!(
+                "(try {} catch {} {})",
+                body.to_string(),
+                name.lexeme,
+                handler.to_string()
+            ),
+            Stmt::Enum { name, variants } => format!(
+                "(enum {} {})",
+                name.lexeme,
+                variants.iter().map(|(tag, fields)| match fields {
+                    Some(fields) => format!(
+                        "({} {})",
+                        tag.lexeme,
+                        fields.iter().map(|field| field.lexeme.clone()).collect::<Vec<String>>().join(" ")
+                    ),
+                    None => tag.lexeme.clone(),
+                }).collect::<Vec<String>>().join(" ")
+            ),
+            Stmt::Class { name, traits, methods } => format!(
+                "(class {}{} {})",
+                name.lexeme,
+                traits.iter().map(|t| format!(" impl {}", t.lexeme)).collect::<String>(),
+                methods.iter().map(method_name).collect::<Vec<String>>().join(" ")
+            ),
+            Stmt::Trait { name, signatures, defaults } => format!(
+                "(trait {} {})",
+                name.lexeme,
+                signatures
+                    .iter()
+                    .map(|(method, _)| method.lexeme.clone())
+                    .chain(defaults.iter().map(method_name))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+            Stmt::Assert { keyword: _, condition, message: Some(message) } => format!(
+                "(assert {} {})",
+                condition.to_string(),
+                message.to_string()
+            ),
+            Stmt::Assert { keyword: _, condition, message: None } => format!("(assert {})", condition.to_string()),
+            Stmt::Test { keyword: _, name, body } => format!("(test \"{}\" {})", name, body.to_string()),
         }
     }
+}
+
+impl Stmt {
+    // Whether running the statement can reach a `yield` or an `await` that suspends
+    // the enclosing generator or task, nested function bodies are not part of it
+    pub fn can_suspend(&self) -> bool {
+        match self {
+            Stmt::Yield { .. } => true,
+            Stmt::Block { statements } => statements.iter().any(|stmt| stmt.can_suspend()),
+            Stmt::If { predicate: _, then, otherwise } => {
+                then.can_suspend() || otherwise.as_ref().is_some_and(|stmt| stmt.can_suspend())
+            },
+            Stmt::While { condition: _, body } => body.can_suspend(),
+            Stmt::ForIn { keyword: _, pattern: _, iterable: _, body } => body.can_suspend(),
+            Stmt::Try { body, name: _, handler } => body.can_suspend() || handler.can_suspend(),
+            _ => self.awaited().is_some(),
+        }
+    }
+
+    // The awaited expression of the statement forms an `await` can suspend in:
+    // `await x;`, `let v = await x;`, `v = await x;` and `return await x;`
+    pub fn awaited(&self) -> Option<&Expr> {
+        let expression = match self {
+            Stmt::Expression { expression: Expr::Assign { name: _, value } } => value.as_ref(),
+            Stmt::Expression { expression } => expression,
+            Stmt::Let { initializer, .. } => initializer,
+            Stmt::Return { keyword: _, value: Some(value) } => value,
+            _ => return None,
+        };
+
+        match expression {
+            Expr::Await { keyword: _, value } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn method_name(method: &Stmt) -> String {
+    match method {
+        Stmt::Function { name, .. } => name.lexeme.clone(),
+        _ => String::from("?"),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::sandbox;
+use std::cell::RefCell;
+use std::io::{self, BufRead, Read, Write};
+use std::rc::Rc;
+
+// Where the output of print, println and echo goes and where read and readln
+// read from, the process stdout and stdin unless the host swaps them
+pub struct Streams {
+    output: RefCell<Box<dyn Write>>,
+    // None reads the process stdin, which is locked for every read so that the
+    // buffer of it stays shared with the rest of the process
+    input: RefCell<Option<Box<dyn BufRead>>>,
+}
+
+impl Streams {
+    pub fn stdio() -> Self {
+        Self {
+            output: RefCell::new(Box::new(io::stdout())),
+            input: RefCell::new(None),
+        }
+    }
+
+    pub fn set_output(&self, output: Box<dyn Write>) {
+        *self.output.borrow_mut() = output;
+    }
+
+    pub fn set_input(&self, input: Box<dyn BufRead>) {
+        *self.input.borrow_mut() = Some(input);
+    }
+
+    pub fn write(&self, text: &str) -> Result<(), String> {
+        self.output
+            .borrow_mut()
+            .write_all(text.as_bytes())
+            .map_err(|_| String::from("Failed to write to the output"))
+    }
+
+    pub fn read_word(&self) -> Result<String, String> {
+        self.with_input(|input| read_word(input))
+    }
+
+    // Reads a line without its line ending, stops reading once the line is over the size limit
+    pub fn read_line(&self) -> Result<String, String> {
+        let mut line = Vec::new();
+        // Room for a line ending after the longest line allowed
+        let limit = sandbox::max_size().map_or(u64::MAX, |max_size| max_size as u64 + 2);
+
+        self.with_input(|input| input.take(limit).read_until(b'\n', &mut line))
+            .map_err(|_| String::from("Failed to read line from stdin"))?;
+        let line = String::from_utf8(line).map_err(|_| String::from("Failed to read line from stdin"))?;
+
+        let line = line.trim_end().to_string();
+        sandbox::check_size("String", line.len())?;
+
+        Ok(line)
+    }
+
+    fn with_input<T>(&self, read: impl FnOnce(&mut dyn BufRead) -> T) -> T {
+        match self.input.borrow_mut().as_mut() {
+            Some(input) => read(input.as_mut()),
+            None => read(&mut io::stdin().lock()),
+        }
+    }
+}
+
+// Skips leading whitespace and reads until the next whitespace, which is consumed too,
+// or the end of the input
+fn read_word(input: &mut dyn BufRead) -> Result<String, String> {
+    let mut word = Vec::new();
+
+    loop {
+        let buffer = input.fill_buf().map_err(|_| String::from("Failed to read from stdin"))?;
+        let Some(&byte) = buffer.first() else {
+            break;
+        };
+
+        input.consume(1);
+        if !byte.is_ascii_whitespace() {
+            word.push(byte);
+            sandbox::check_size("String", word.len())?;
+        } else if !word.is_empty() {
+            break;
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&word).into_owned())
+}
+
+// Writer that keeps everything written to it, clones share the same buffer
+#[derive(Clone, Default)]
+pub struct OutputBuffer {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.bytes.borrow()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_and_lines_are_read_from_the_input() {
+        let streams = Streams::stdio();
+        streams.set_input(Box::new(io::Cursor::new("  one two\nthree four\n")));
+
+        assert_eq!(streams.read_word().unwrap(), "one");
+        assert_eq!(streams.read_line().unwrap(), "two");
+        assert_eq!(streams.read_line().unwrap(), "three four");
+        assert_eq!(streams.read_word().unwrap(), "");
+    }
+}
@@ -0,0 +1,165 @@
+// Runs every script in cases/ in process and compares what it did with the
+// expectation comments in it:
+//
+//   // expect: text        next line the script prints
+//   // expect-error: text  next line of the error, the exit status is 70 then like
+//                          for an uncaught runtime error
+//   // expect-exit: code   exit status when it is not the default, 65 for syntax errors
+//   // max-depth: depth    limit on nested calls, like the --max-depth flag
+//
+// A `name.stdin` file next to `name.zw` is fed to read and readln. Trailing
+// whitespace is ignored when lines are compared.
+
+use std::cell::Cell;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use zawa::{Engine, OutputBuffer};
+
+// Native stack for the thread each case runs on, scripts recurse natively
+const CASE_STACK_SIZE: usize = 512 * 1024 * 1024;
+
+struct Expectations {
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+    exit_code: i32,
+    max_depth: Option<usize>,
+}
+
+struct Outcome {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
+}
+
+fn expectations(source: &str) -> Expectations {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_code = None;
+    let mut max_depth = None;
+
+    for line in source.lines() {
+        let Some((_, comment)) = line.split_once("// ") else {
+            continue;
+        };
+
+        if let Some(text) = comment.strip_prefix("expect: ") {
+            stdout.push(text.to_string());
+        } else if let Some(text) = comment.strip_prefix("expect-error: ") {
+            stderr.push(text.to_string());
+        } else if let Some(code) = comment.strip_prefix("expect-exit: ") {
+            exit_code = code.trim().parse().ok();
+        } else if let Some(depth) = comment.strip_prefix("max-depth: ") {
+            max_depth = depth.trim().parse().ok();
+        }
+    }
+
+    let exit_code = exit_code.unwrap_or(if stderr.is_empty() { 0 } else { 70 });
+    Expectations { stdout, stderr, exit_code, max_depth }
+}
+
+fn run_case(source: String, stdin: String, max_depth: Option<usize>) -> Outcome {
+    let mut engine = Engine::new();
+    Engine::set_stack_size(CASE_STACK_SIZE);
+    if let Some(depth) = max_depth {
+        Engine::set_max_depth(depth);
+    }
+
+    let output = OutputBuffer::new();
+    engine.set_output(output.clone());
+    engine.set_input(Cursor::new(stdin));
+
+    // exit() stops the script instead of the test process
+    let exit_code = Rc::new(Cell::new(None));
+    let exit_called = exit_code.clone();
+    engine.register_fn("exit", move |code: i64| -> Result<(), String> {
+        exit_called.set(Some(code as i32));
+        Err(String::from("exit"))
+    });
+
+    // Same statuses as the zawa binary
+    let result = match engine.validate(&source) {
+        Ok(_) => engine.eval(&source).map(|_| ()).map_err(|msg| (msg, 70)),
+        Err(msg) => Err((msg, 65)),
+    };
+
+    let (stderr, status) = match result {
+        Ok(_) => (String::new(), 0),
+        Err(_) if exit_code.get().is_some() => (String::new(), exit_code.get().unwrap_or(0)),
+        Err((msg, status)) => (format!("{}\n", msg), status),
+    };
+
+    Outcome { stdout: output.contents(), stderr, exit_code: status }
+}
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(|line| line.trim_end().to_string()).collect()
+}
+
+fn trimmed(expected: &[String]) -> Vec<String> {
+    expected.iter().map(|line| line.trim_end().to_string()).collect()
+}
+
+// Differences between what the case at `path` was expected to do and what it did
+fn check_case(path: &Path) -> Vec<String> {
+    let source = fs::read_to_string(path).expect("Could not read case");
+    let stdin = fs::read_to_string(path.with_extension("stdin")).unwrap_or_default();
+    let expected = expectations(&source);
+
+    let max_depth = expected.max_depth;
+    let outcome = std::thread::Builder::new()
+        .stack_size(CASE_STACK_SIZE)
+        .spawn(move || run_case(source, stdin, max_depth))
+        .expect("Could not start case thread")
+        .join()
+        .expect("Case panicked");
+
+    let mut failures = Vec::new();
+    if lines(&outcome.stdout) != trimmed(&expected.stdout) {
+        failures.push(format!("stdout was\n{}expected\n{}", outcome.stdout, expected.stdout.join("\n")));
+    }
+    if lines(&outcome.stderr) != trimmed(&expected.stderr) {
+        failures.push(format!("error was\n{}expected\n{}", outcome.stderr, expected.stderr.join("\n")));
+    }
+    if outcome.exit_code != expected.exit_code {
+        failures.push(format!("exit status was {} expected {}", outcome.exit_code, expected.exit_code));
+    }
+
+    failures
+}
+
+fn cases() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/cases");
+    let mut cases: Vec<PathBuf> = fs::read_dir(directory)
+        .expect("Could not read cases directory")
+        .map(|entry| entry.expect("Could not read case entry").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "zw"))
+        .collect();
+
+    cases.sort();
+    cases
+}
+
+#[test]
+fn golden_cases() {
+    let mut failures = Vec::new();
+
+    for case in cases() {
+        for failure in check_case(&case) {
+            failures.push(format!("{}: {}", case.display(), failure));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} failed expectations\n\n{}", failures.len(), failures.join("\n\n"));
+}
+
+#[test]
+fn expectation_comments_are_parsed() {
+    let expected = expectations("println(1); // expect: 1\n// expect: two words \n// expect-error:   in f\n// max-depth: 10\n");
+
+    assert_eq!(expected.stdout, vec!["1", "two words "]);
+    assert_eq!(expected.stderr, vec!["  in f"]);
+    assert_eq!(expected.exit_code, 70);
+    assert_eq!(expected.max_depth, Some(10));
+}
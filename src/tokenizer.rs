@@ -32,13 +32,35 @@ fn get_keyword_hashmap() -> HashMap<&'static str, TokenType> {
         ("echo", TokenType::Echo),
         ("super", TokenType::Super),
         ("let", TokenType::Let),
-        ("break", TokenType::Break)
+        ("const", TokenType::Const),
+        ("break", TokenType::Break),
+        ("match", TokenType::Match),
+        ("enum", TokenType::Enum),
+        ("trait", TokenType::Trait),
+        ("impl", TokenType::Impl),
+        ("in", TokenType::In),
+        ("yield", TokenType::Yield),
+        ("async", TokenType::Async),
+        ("await", TokenType::Await),
+        ("try", TokenType::Try),
+        ("catch", TokenType::Catch),
+        ("assert", TokenType::Assert)
     ])
 }
 
+// Words that can not be used as names, sorted
+pub fn keywords() -> Vec<&'static str> {
+    let mut keywords: Vec<&'static str> = get_keyword_hashmap().into_keys().collect();
+    keywords.sort();
+
+    keywords
+}
+
 pub struct Tokenizer {
     source: String,
     tokens: Vec<Token>,
+    // Comments and the shebang line, the parser never sees them
+    comments: Vec<Comment>,
     start: usize,
     current: usize,
     line: usize,
@@ -51,9 +73,10 @@ impl Tokenizer {
         Self {
             source: source.to_string(),
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
-            line: 0,
+            line: 1,
             keywords: get_keyword_hashmap(),
         }
     }
@@ -89,6 +112,11 @@ impl Tokenizer {
         Ok(self.tokens.clone())
     }
 
+    // Comments found by the last tokenize, in the order they appear
+    pub fn comments(&self) -> Vec<Comment> {
+        self.comments.clone()
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
@@ -101,9 +129,35 @@ impl Tokenizer {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
+            ':' => self.add_token(TokenType::Colon),
+            '|' => self.add_token(TokenType::Pipe),
+            '.' => {
+                let token = if self.char_match('.') {
+                    if self.char_match('=') {
+                        TokenType::DotDotEqual
+                    } else if self.char_match('.') {
+                        TokenType::DotDotDot
+                    } else {
+                        TokenType::DotDot
+                    }
+                } else {
+                    TokenType::Dot
+                };
+
+                self.add_token(token);
+            },
+            '-' => {
+                let token = if self.char_match('>') {
+                    TokenType::Arrow
+                } else {
+                    TokenType::Minus
+                };
+
+                self.add_token(token);
+            },
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::Semicolon),
             '*' => self.add_token(TokenType::Star),
@@ -119,6 +173,8 @@ impl Tokenizer {
             '=' => {
                 let token = if self.char_match('=') {
                     TokenType::EqualEqual
+                } else if self.char_match('>') {
+                    TokenType::FatArrow
                 } else {
                     TokenType::Equal
                 };
@@ -151,11 +207,20 @@ impl Tokenizer {
                         }
                         self.advance();
                     }
+                    self.add_comment();
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             },
 
+            // Shebang line of a script run as an executable
+            '#' if self.start == 0 && self.peek() == '!' => {
+                while self.peek() != '\n' && !self.is_at_end() {
+                    self.advance();
+                }
+                self.add_comment();
+            },
+
             ' ' | '\t' | '\r' => {},
             '\n' => self.line += 1,
             '"' => self.string()?,
@@ -263,6 +328,17 @@ impl Tokenizer {
         c
     }
 
+    fn add_comment(&mut self) {
+        // Positions count chars, comments are where non ascii text is most likely
+        let text: String = self.source.chars().skip(self.start).take(self.current - self.start).collect();
+
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            line_number: self.line,
+            token: self.tokens.len(),
+        })
+    }
+
     fn add_token(&mut self, token_type: TokenType) {
         self.add_token_lit(token_type, None);
     }
@@ -285,9 +361,17 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
+    Pipe,
     Dot,
+    DotDot,
+    DotDotEqual,
+    DotDotDot,
     Minus,
+    Arrow,
     Plus,
     Semicolon,
     Slash,
@@ -297,6 +381,7 @@ pub enum TokenType {
     BangEqual,
     Equal,
     EqualEqual,
+    FatArrow,
     Greater,
     GreaterEqual,
     Less,
@@ -322,7 +407,19 @@ pub enum TokenType {
     Echo,
     Super,
     Let,
+    Const,
     Break,
+    Match,
+    Enum,
+    Trait,
+    Impl,
+    In,
+    Yield,
+    Async,
+    Await,
+    Try,
+    Catch,
+    Assert,
 
     Eof
 }
@@ -339,6 +436,14 @@ pub enum LiteralValue {
     StringValue(String)
 }
 
+// A comment sits before the token at index `token` of the tokenized source
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,
+    pub line_number: usize,
+    pub token: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
@@ -0,0 +1,142 @@
+// Runs the zawa binary the way a user would, for what only the command line does:
+// exit statuses, arguments, stdin and the test and fmt commands
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+fn zawa(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zawa")).args(args).output().unwrap()
+}
+
+fn case(name: &str) -> String {
+    format!("{}/src/tests/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// Directory of its own for a test run, removed when the test is done
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(test: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("zawa_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        TempDir(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn exit_statuses() {
+    let status = |args: &[&str]| zawa(args).status.code();
+
+    assert_eq!(status(&["-e", "println(1);"]), Some(0));
+    assert_eq!(status(&["--wat"]), Some(64));
+    assert_eq!(status(&["-e", "let = 1;"]), Some(65));
+    assert_eq!(status(&[&case("cases/missing.zw")]), Some(66));
+    assert_eq!(status(&["-e", "println(nope);"]), Some(70));
+}
+
+#[test]
+fn deep_recursion_is_a_runtime_error() {
+    let scripts = [
+        "fn deep(n) { if (n == 0) { return 0; } return 1 + deep(n - 1); } println(deep(6000));",
+        "class N { fn init(self, n) { self.n = n; } fn __add__(self, other) { if (self.n == 0) { return 0; } return 1 + (N(self.n - 1) + other); } } println(N(6000) + 1);",
+        "fn deep(n) { return match (n) { 0 => 0, _ => 1 + deep(n - 1), }; } println(deep(6000));",
+    ];
+
+    for script in scripts {
+        let output = zawa(&["-e", script]);
+        let stderr = std::str::from_utf8(output.stderr.as_slice()).unwrap();
+
+        assert_eq!(output.status.code(), Some(70), "{}", stderr);
+        assert!(stderr.starts_with("Error: line: 1, Stack overflow"), "{}", stderr);
+    }
+}
+
+#[test]
+fn check_types() {
+    let output = zawa(&["check", &case("cases/check.zw")]);
+
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stdout.is_empty());
+
+    let errors = std::str::from_utf8(output.stderr.as_slice())
+        .unwrap()
+        .split("\n")
+        .collect::<Vec<&str>>();
+
+    let error_ans = &[
+        "Error: line: 6, Function name should return String but returns Number",
+        "line: 9, Argument b of add should be Number but is String",
+        "line: 10, text is declared as String but initialised with Number",
+        "line: 11, Binary operator Plus cannot be applied for operands of type String and Number",
+        "line: 12, Can not assign String to total of type Number",
+        "line: 16, Enum Shape has no variant Square",
+        "line: 17, Unknown type wat"
+    ];
+    assert_eq!(errors.len(), error_ans.len() + 1);
+    for i in 0..(errors.len() - 1) {
+        assert_eq!(errors[i], error_ans[i]);
+    }
+}
+
+#[test]
+fn test_command_runs_every_test() {
+    let output = zawa(&["test", &case("projects/math")]);
+
+    let stdout = std::str::from_utf8(output.stdout.as_slice()).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("math.zw: add ... ok"));
+    assert!(stdout.contains("math.zw: halve ... FAILED"));
+    assert!(stdout.contains("math.zw: halve declared on line 14\nline: 16, Assertion failed: half of 5 is 2.5"));
+    assert!(stdout.contains("state.zw: count starts at zero ... ok"));
+    assert!(stdout.contains("state.zw: tests do not share globals ... ok"));
+    assert!(stdout.ends_with("test result: FAILED. 3 passed; 1 failed\n"));
+}
+
+#[test]
+fn script_arguments_and_inline_code() {
+    let output = zawa(&["-e", "println(args()); println(len(args()));", "--", "one", "--two"]);
+
+    assert_eq!(std::str::from_utf8(output.stdout.as_slice()).unwrap(), "[\"one\", \"--two\"]\n2\n");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zawa"))
+        .args(["run", "-", "x"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"println(args());").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(std::str::from_utf8(output.stdout.as_slice()).unwrap(), "[\"x\"]\n");
+}
+
+#[test]
+fn fmt_writes_files_and_check_lists_them() {
+    let dir = TempDir::new("fmt_writes_files_and_check_lists_them");
+    let path = dir.0.join("messy.zw");
+    std::fs::write(&path, "let a=1;// one\nfn f(){return a;}\n").unwrap();
+
+    let check = |path: &Path| zawa(&["fmt", "--check", path.to_str().unwrap()]);
+
+    let output = check(&dir.0);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(std::str::from_utf8(output.stdout.as_slice()).unwrap(), format!("{}\n", path.display()));
+
+    let output = zawa(&["fmt", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "let a = 1; // one\nfn f() {\n    return a;\n}\n");
+    assert_eq!(check(&path).status.code(), Some(0));
+
+    std::fs::write(&path, "let = 1;").unwrap();
+    assert_eq!(check(&path).status.code(), Some(65));
+}